@@ -0,0 +1,297 @@
+use crate::output::{CliError, Failure, Output, say};
+use crate::{downloader, load_project, settings};
+use clap::Subcommand;
+use gdm::project::Project;
+use gdm::project::config::ProjectConfiguration;
+use gdm::project::diagnostics::{self, Severity};
+use gdm::project::extends;
+use gdm::project::format::ConfigFormat;
+use gdm::settings::Settings;
+use gdm::util::dirs;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    #[clap(about = "Print the JSON Schema of the project configuration")]
+    Schema,
+    #[clap(about = "Check the project configuration for errors and unknown keys, e.g. in CI")]
+    Validate {
+        path: Option<PathBuf>,
+
+        #[clap(long, help = "Fail on warnings too")]
+        strict: bool,
+    },
+    #[clap(
+        about = "Print a key like `mono` or `runs.game.mode`, or all of them when no key is given"
+    )]
+    Get {
+        key: Option<String>,
+
+        #[clap(long)]
+        path: Option<PathBuf>,
+    },
+    #[clap(
+        about = "Change a key, e.g. `gdm config set mono true`, values are read as JSON or else as a string"
+    )]
+    Set {
+        key: String,
+        value: String,
+
+        #[clap(long)]
+        path: Option<PathBuf>,
+    },
+    #[clap(about = "Remove a key, restoring its default")]
+    Unset {
+        key: String,
+
+        #[clap(long)]
+        path: Option<PathBuf>,
+    },
+    #[clap(about = "Print the project's configuration")]
+    Show {
+        #[clap(
+            long,
+            help = "Print the effective configuration, including what it inherits through `extends`"
+        )]
+        resolved: bool,
+
+        #[clap(long)]
+        path: Option<PathBuf>,
+    },
+    #[clap(about = "Download the configurations the project extends by URL, or update them")]
+    Fetch { path: Option<PathBuf> },
+    #[clap(
+        about = "Write the configuration in another format, e.g. .tool-versions for asdf and mise"
+    )]
+    Export {
+        path: Option<PathBuf>,
+
+        #[clap(
+            long,
+            help = "json, toml, godot, godot-version or tool-versions, the last two only receive the engine version"
+        )]
+        format: ConfigFormat,
+    },
+}
+
+pub async fn run(
+    command: ConfigCommands,
+    global: bool,
+    project_dir: Option<PathBuf>,
+    out: &Output,
+) -> Result<Value, CliError> {
+    match command {
+        ConfigCommands::Schema
+        | ConfigCommands::Validate { .. }
+        | ConfigCommands::Show { .. }
+        | ConfigCommands::Fetch { .. }
+        | ConfigCommands::Export { .. }
+            if global =>
+        {
+            Err(CliError::new(
+                Failure::General,
+                "--global only applies to get, set and unset",
+            ))
+        }
+        ConfigCommands::Get { key, path } if !global => {
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let project = load_project(&dirs)?;
+            let Some(key) = key else {
+                let config = serde_json::to_value(&project.config)?;
+                for (key, value) in config.as_object().into_iter().flatten() {
+                    say!(out, "{key} = {value}");
+                }
+                return Ok(config);
+            };
+
+            let value = project.config.get(&key)?.unwrap_or(Value::Null);
+            print_value(out, &key, &value);
+            Ok(json!({ "key": key, "value": value }))
+        }
+        ConfigCommands::Set { key, value, path } if !global => {
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let mut project = load_project(&dirs)?;
+            project.set(&key, Some(&value))?;
+
+            let value = project.config.get(&key)?.unwrap_or(Value::Null);
+            say!(
+                out,
+                "Set `{key}` to {value} in {}",
+                project.config_path().display()
+            );
+            Ok(json!({ "key": key, "value": value, "path": project.config_path() }))
+        }
+        ConfigCommands::Unset { key, path } if !global => {
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let mut project = load_project(&dirs)?;
+            project.set(&key, None)?;
+
+            say!(
+                out,
+                "Removed `{key}` from {}",
+                project.config_path().display()
+            );
+            Ok(json!({ "key": key, "path": project.config_path() }))
+        }
+        ConfigCommands::Get { key: Some(key), .. } => {
+            let value = settings()?.get(&key)?.unwrap_or(Value::Null);
+            print_value(out, &key, &value);
+            Ok(json!({ "key": key, "value": value }))
+        }
+        ConfigCommands::Get { key: None, .. } => {
+            let mut settings = serde_json::to_value(settings()?)?;
+            // printed by `get github_token` only, so listing the settings does not leak it
+            if let Some(token) = settings.get_mut("github_token") {
+                *token = json!("********");
+            }
+            for (key, value) in settings.as_object().into_iter().flatten() {
+                say!(out, "{key} = {value}");
+            }
+            Ok(settings)
+        }
+        ConfigCommands::Set { key, value, .. } => {
+            let home = dirs::user_home();
+            let settings = Settings::set(home.as_deref(), &key, Some(&value))?;
+            let path = Settings::path(home.as_deref())?;
+            let value = settings.get(&key)?.unwrap_or(Value::Null);
+            say!(out, "Set `{key}` to {value} in {}", path.display());
+            Ok(json!({ "key": key, "value": value, "path": path }))
+        }
+        ConfigCommands::Unset { key, .. } => {
+            let home = dirs::user_home();
+            Settings::set(home.as_deref(), &key, None)?;
+            let path = Settings::path(home.as_deref())?;
+            say!(out, "Removed `{key}` from {}", path.display());
+            Ok(json!({ "key": key, "path": path }))
+        }
+        ConfigCommands::Schema => {
+            let schema = serde_json::to_value(ProjectConfiguration::json_schema())?;
+            say!(out, "{}", serde_json::to_string_pretty(&schema)?);
+            Ok(schema)
+        }
+        ConfigCommands::Validate { path, strict } => {
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let Some(&format) = ConfigFormat::detect(&dirs.absolute_project_dir).first() else {
+                return Err(gdm::Error::ProjectNotFound {
+                    path: dirs.absolute_project_dir,
+                }
+                .into());
+            };
+            let config_path = dirs.absolute_project_dir.join(format.file_name());
+
+            let text = fs::read_to_string(&config_path)?;
+            let base = extends::resolve(&text, format, &dirs.absolute_project_dir, &dirs)?;
+            let (config, mut diagnostics) =
+                diagnostics::check(&text, format, base.as_ref().map(|base| &base.value))?;
+            if let Some(config) = &config {
+                diagnostics.extend(diagnostics::check_tasks(config, &text, format));
+            }
+            for diagnostic in &diagnostics {
+                let severity = match diagnostic.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                };
+                say!(out, "{}: {severity}: {diagnostic}", config_path.display());
+            }
+
+            let errors = diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                .count();
+            let warnings = diagnostics.len() - errors;
+            let result = json!({
+                "path": config_path,
+                "errors": errors,
+                "warnings": warnings,
+                "diagnostics": diagnostics,
+            });
+
+            if errors > 0 || (strict && warnings > 0) {
+                return Err(CliError::new(
+                    Failure::General,
+                    format!(
+                        "{} is invalid: {errors} error(s), {warnings} warning(s)",
+                        config_path.display()
+                    ),
+                )
+                .with_details(result));
+            }
+
+            say!(
+                out,
+                "{} is valid ({warnings} warning(s))",
+                config_path.display()
+            );
+            Ok(result)
+        }
+        ConfigCommands::Show { resolved, path } => {
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let project = load_project(&dirs)?;
+            let config = match resolved {
+                true => serde_json::to_value(&project.config)?,
+                false => project.own_config(),
+            };
+
+            if resolved && let Some(base) = project.base() {
+                for source in &base.sources {
+                    eprintln!("Extends {source}");
+                }
+            }
+            say!(out, "{}", serde_json::to_string_pretty(&config)?);
+            Ok(config)
+        }
+        ConfigCommands::Fetch { path } => {
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let Some(&format) = ConfigFormat::detect(&dirs.absolute_project_dir).first() else {
+                return Err(gdm::Error::ProjectNotFound {
+                    path: dirs.absolute_project_dir,
+                }
+                .into());
+            };
+            let text = fs::read_to_string(dirs.absolute_project_dir.join(format.file_name()))?;
+            let fetched = extends::fetch(
+                &text,
+                format,
+                &dirs.absolute_project_dir,
+                &dirs,
+                &downloader()?,
+            )
+            .await?;
+
+            for url in &fetched {
+                say!(out, "Downloaded {url}");
+            }
+            if fetched.is_empty() {
+                say!(out, "The configuration extends no URLs");
+            }
+            Ok(json!({ "fetched": fetched }))
+        }
+        ConfigCommands::Export { path, format } => {
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let project = Project::load(&dirs)?;
+            let export_path = project.export_config(format)?;
+
+            say!(
+                out,
+                "Wrote the configuration of {} to {}",
+                project.name,
+                export_path.display()
+            );
+            Ok(json!({
+                "project": project.name,
+                "format": format.to_string(),
+                "path": export_path,
+            }))
+        }
+    }
+}
+
+fn print_value(out: &Output, key: &str, value: &Value) {
+    match value {
+        Value::Null => say!(out, "`{key}` is not set"),
+        Value::String(value) => say!(out, "{value}"),
+        value => say!(out, "{value}"),
+    }
+}
@@ -0,0 +1,1034 @@
+use clap::{Parser, Subcommand};
+use const_format::concatcp;
+use gdm::project::config::{ProjectConfiguration, UpgradePolicy};
+use gdm::project::engine::EngineVersion;
+use gdm::project::export::{self, ExportMode};
+use gdm::project::format::ConfigFormat;
+use gdm::project::hooks::{self, Hook};
+use gdm::project::launch::{LaunchMode, LaunchOptions};
+use gdm::project::versions;
+use gdm::project::{inspect, logs, migrations, tasks};
+use gdm::settings::Settings;
+use gdm::util::download::Downloader;
+use gdm::util::{dirs, process};
+use gdm::workspace::{self, Workspace};
+use gdm::{built_info, project};
+use indicatif::HumanBytes;
+use output::{Classify, CliError, Failure, Output, fail, say};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+mod config;
+mod output;
+mod progress;
+
+const ABOUT: &str = concatcp!(
+    built_info::PKG_DESCRIPTION,
+    "\n - ",
+    built_info::PKG_HOMEPAGE,
+    "\ngit: ",
+    built_info::GIT_VERSION.unwrap()
+);
+
+#[derive(Parser)]
+#[command(author = gdm::built_info::PKG_AUTHORS, version = gdm::VERSION, about = ABOUT)]
+#[command(next_line_help = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+
+    #[clap(
+        long,
+        global = true,
+        help = "Print the result as a single JSON document, see the README for its format"
+    )]
+    json: bool,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GDM_PROJECT",
+        help = "The project to work on, instead of the nearest one from the current directory up"
+    )]
+    project: Option<PathBuf>,
+
+    #[clap(
+        long,
+        short = 'p',
+        global = true,
+        value_name = "MEMBER",
+        help = "The member of the workspace to work on, by its directory"
+    )]
+    member: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    #[clap(about = "Upgrade Godot Engine to the latest version the `upgrade` setting allows")]
+    Upgrade {
+        path: Option<PathBuf>,
+
+        #[clap(long, help = "Upgrade every member of the workspace")]
+        workspace: bool,
+    },
+    #[clap()]
+    Set {
+        version: String,
+        path: Option<PathBuf>,
+    },
+
+    #[clap(about = "Initialize a new project")]
+    Init {
+        path: Option<PathBuf>,
+
+        #[clap(
+            long,
+            help = "Use Mono version of Godot Engine, the default when `mono` is set globally"
+        )]
+        mono: bool,
+
+        #[clap(
+            long,
+            default_value_t = ConfigFormat::Json,
+            help = "Where to store the configuration: json (project.json), toml (gdm.toml), godot (a [gdm] section in project.godot), godot-version or tool-versions"
+        )]
+        format: ConfigFormat,
+    },
+    #[clap(about = "Launch Godot Engine")]
+    Run {
+        #[clap(
+            value_name = "NAME|PATH",
+            help = "Name of a run configuration from the project configuration, or the project directory",
+            long_help = "Name of a run configuration from the project configuration, or the project directory. Run configurations take precedence over a directory of the same name, pass the directory with --path to open it instead"
+        )]
+        target: Option<String>,
+
+        #[clap(
+            long,
+            help = "Project directory, when also passing a run configuration"
+        )]
+        path: Option<PathBuf>,
+
+        #[clap(long, help = "Run the engine in console mode")]
+        console: bool,
+
+        #[clap(long, group = "mode", help = "Open the editor (default)")]
+        editor: bool,
+
+        #[clap(
+            long,
+            group = "mode",
+            value_name = "SCENE",
+            num_args = 0..=1,
+            help = "Run the game, optionally starting with the given scene"
+        )]
+        game: Option<Option<String>>,
+
+        #[clap(long, group = "mode", help = "Run the game without a window")]
+        headless: bool,
+
+        #[clap(long, group = "mode", help = "Open the project manager")]
+        project_manager: bool,
+
+        #[clap(
+            long,
+            short = 's',
+            group = "mode",
+            value_name = "FILE",
+            help = "Run a script without a window"
+        )]
+        script: Option<PathBuf>,
+
+        #[clap(
+            long,
+            conflicts_with = "detach",
+            help = "Wait for the engine to exit and return its exit code (default for headless and script runs)"
+        )]
+        attach: bool,
+
+        #[clap(long, help = "Return right after launching the engine")]
+        detach: bool,
+
+        #[clap(long, help = "Write the engine output to a log file, see `gdm logs`")]
+        log: bool,
+
+        #[clap(last = true, help = "Additional arguments passed to the engine")]
+        args: Vec<String>,
+    },
+    #[clap(about = "Print the path of the engine binary `gdm run` launches")]
+    Which {
+        path: Option<PathBuf>,
+
+        #[clap(long, help = "Print the console binary instead")]
+        console: bool,
+    },
+    #[clap(about = "Show the project's configuration and engine installation")]
+    Info { path: Option<PathBuf> },
+    #[clap(
+        about = "Run a command with the project's engine in GODOT, GODOT4_BIN (not for Godot 3), GODOT_VERSION and GODOT_MONO"
+    )]
+    Exec {
+        #[clap(long)]
+        path: Option<PathBuf>,
+
+        #[clap(last = true, required = true, help = "Command and arguments to run")]
+        command: Vec<String>,
+    },
+    #[clap(
+        about = "Run a task from the project configuration, or list the tasks when no name is given"
+    )]
+    Task {
+        name: Option<String>,
+
+        #[clap(long)]
+        path: Option<PathBuf>,
+    },
+    #[clap(about = "Show the engine output of previous runs started with `--log`")]
+    Logs {
+        path: Option<PathBuf>,
+
+        #[clap(
+            long,
+            short = 'f',
+            help = "Keep printing new output as it is written, until interrupted"
+        )]
+        follow: bool,
+
+        #[clap(
+            long,
+            value_name = "N",
+            default_value_t = 1,
+            help = "Show the Nth most recent run, 1 being the latest"
+        )]
+        run: usize,
+
+        #[clap(long, help = "List the available logs")]
+        list: bool,
+    },
+    #[clap(about = "Export the project using the presets from export_presets.cfg")]
+    Export {
+        #[clap(help = "Names of the presets to export, defaults to all presets")]
+        presets: Vec<String>,
+
+        #[clap(
+            long,
+            help = "Export release builds (default)",
+            conflicts_with = "debug"
+        )]
+        release: bool,
+
+        #[clap(long, help = "Export debug builds")]
+        debug: bool,
+
+        #[clap(long, help = "Directory to place the exported files in")]
+        out: Option<PathBuf>,
+
+        #[clap(long, help = "List the export presets and exit")]
+        list: bool,
+
+        #[clap(long)]
+        path: Option<PathBuf>,
+    },
+    #[clap(about = "Inspect and check the project's configuration and the user's settings")]
+    Config {
+        #[clap(
+            long,
+            global = true,
+            help = "Use the user's settings instead of the project's"
+        )]
+        global: bool,
+
+        #[command(subcommand)]
+        command: config::ConfigCommands,
+    },
+    #[clap(about = "Uninstall all engine versions and clear download cache")]
+    Clean,
+    #[clap(about = "Manage the installed engines")]
+    Engine {
+        #[command(subcommand)]
+        command: EngineCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum EngineCommands {
+    #[clap(about = "Install the project's engine unless it is installed already")]
+    Install {
+        path: Option<PathBuf>,
+
+        #[clap(long, help = "Install the engines of every member of the workspace")]
+        workspace: bool,
+    },
+}
+
+#[dotenvy::load(required = false)]
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let output = Output {
+        json: cli.json,
+        progress: progress::for_terminal(cli.json),
+    };
+
+    let result = match project_dir(cli.project, cli.member) {
+        Ok(project_dir) => run(cli.command, project_dir, &output).await,
+        Err(error) => Err(error),
+    };
+    std::process::exit(output.finish(result));
+}
+
+fn load_project(dirs: &dirs::Dirs) -> Result<project::Project, CliError> {
+    let project = project::Project::load(dirs)?;
+    for warning in &project.warnings {
+        eprintln!("Warning: {}: {warning}", project.config_path().display());
+    }
+    Ok(project)
+}
+
+/// Whether the project `gdm run` finds without a path has the run configuration `name`.
+async fn has_run(project_dir: Option<PathBuf>, name: &str) -> bool {
+    let Ok(dirs) = dirs::init(project_dir).await else {
+        return false;
+    };
+    project::Project::load(&dirs).is_ok_and(|project| project.config.runs.contains_key(name))
+}
+
+/// The project set with `--project` or `GDM_PROJECT`, or the `--member` of the workspace around it.
+fn project_dir(
+    project: Option<PathBuf>,
+    member: Option<String>,
+) -> Result<Option<PathBuf>, CliError> {
+    let Some(member) = member else {
+        return Ok(project);
+    };
+    let workspace = find_workspace(project)?;
+    match workspace.member(&member) {
+        Some(dir) => Ok(Some(dir)),
+        None => fail!(
+            Failure::NotFound,
+            "Unknown workspace member \"{member}\", available members: {}",
+            workspace
+                .members
+                .iter()
+                .map(|member| member.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// The workspace around `start`, or around the current directory.
+fn find_workspace(start: Option<PathBuf>) -> Result<Workspace, CliError> {
+    let start = match start {
+        Some(start) => std::path::absolute(start)?,
+        None => env::current_dir()?,
+    };
+    match Workspace::find(&start)? {
+        Some(workspace) => Ok(workspace),
+        None => fail!(
+            Failure::NotFound,
+            "No {} found in {} or its parents",
+            workspace::FILE_NAME,
+            start.display()
+        ),
+    }
+}
+
+fn settings() -> Result<Settings, CliError> {
+    Ok(Settings::load(dirs::user_home().as_deref())?)
+}
+
+fn downloader() -> Result<Downloader, CliError> {
+    Ok(settings()?.downloader()?)
+}
+
+async fn ensure_installed(project: &project::Project, out: &Output) -> Result<bool, CliError> {
+    Ok(versions::ensure_version_installed(project, &downloader()?, &*out.progress).await?)
+}
+
+/// Tells what a new project took from an existing project.godot, returned as `inferred`.
+fn report_inferred(project: &project::Project, out: &Output) -> Result<Value, CliError> {
+    let Some(existing) = inspect::inspect(&project.path())? else {
+        return Ok(Value::Null);
+    };
+
+    if let Some(hint) = &existing.version_hint {
+        let inferred = existing
+            .version
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(".");
+        let found = migrations::parse_version(&project.config.version.version_string).is_some_and(
+            |parts| {
+                parts
+                    .iter()
+                    .chain([0; 3].iter())
+                    .take(existing.version.len())
+                    .eq(existing.version.iter())
+            },
+        );
+        if found {
+            say!(
+                out,
+                "project.godot is made for Godot {inferred} ({hint}), using v{}",
+                project.config.version
+            );
+        } else {
+            say!(
+                out,
+                "project.godot is made for Godot {inferred} ({hint}), but no such release was found, using v{}",
+                project.config.version
+            );
+        }
+    }
+    if let Some(hint) = &existing.mono_hint {
+        say!(out, "The project uses C# ({hint}), using the Mono build");
+    }
+
+    Ok(serde_json::to_value(existing)?)
+}
+
+/// Versions `gdm upgrade` resolved by download source, current version and policy.
+type Resolved = HashMap<(String, String, UpgradePolicy), String>;
+
+/// The version `gdm upgrade` moves the project to, looked up once for projects alike.
+async fn upgrade_version(
+    project: &project::Project,
+    settings: &Settings,
+    resolved: &mut Resolved,
+) -> Result<EngineVersion, CliError> {
+    let config = &project.config;
+    let policy = config.upgrade.unwrap_or(settings.upgrade);
+    let current = match policy {
+        UpgradePolicy::Latest => String::new(),
+        _ => config.version.to_string(),
+    };
+    let key = (config.download_source.to_string(), current, policy);
+    if let Some(version) = resolved.get(&key) {
+        return Ok(EngineVersion::from_string(version.clone()));
+    }
+
+    let version = config
+        .download_source
+        .get_upgrade_version(&config.version, policy, &settings.downloader()?)
+        .await?;
+    resolved.insert(key, version.to_string());
+    Ok(version)
+}
+
+/// Moves the project to `version`, running its upgrade hooks around the change.
+async fn upgrade(
+    project: &mut project::Project,
+    version: EngineVersion,
+    out: &Output,
+) -> Result<Value, CliError> {
+    say!(out, "Found latest version: {version}");
+    let previous_version = project.config.version.to_string();
+    let upgraded = version != project.config.version;
+    if upgraded {
+        hooks::run(project, Hook::PreUpgrade, &*out.progress).await?;
+
+        project.config.version = version;
+        project.save()?;
+        say!(
+            out,
+            "Successfully upgraded Godot Engine to v{engine_version}",
+            engine_version = project.config.version
+        );
+
+        // post_upgrade hooks usually need the new engine, e.g. to regenerate bindings
+        if !project.config.hooks.post_upgrade.is_empty() {
+            ensure_installed(project, out).await?;
+        }
+        hooks::run(project, Hook::PostUpgrade, &*out.progress).await?;
+    } else {
+        say!(out, "Project is already up to date!");
+    }
+
+    Ok(json!({
+        "project": project.name,
+        "previous_version": previous_version,
+        "version": project.config.version.to_string(),
+        "upgraded": upgraded,
+        "initialized": false,
+    }))
+}
+
+/// Runs a command, `project_dir` is the project set with `--project` or `GDM_PROJECT`.
+async fn run(
+    command: Commands,
+    project_dir: Option<PathBuf>,
+    out: &Output,
+) -> Result<Value, CliError> {
+    match command {
+        Commands::Upgrade {
+            path,
+            workspace: true,
+        } => {
+            let workspace = find_workspace(path.or(project_dir))?;
+            let settings = settings()?;
+
+            // every member is resolved before any of them changes, members on the same engine move together
+            let mut resolved = HashMap::new();
+            let mut members = Vec::new();
+            for dir in workspace.member_dirs() {
+                let project = load_project(&dirs::init(Some(dir)).await?)?;
+                let version = upgrade_version(&project, &settings, &mut resolved).await?;
+                members.push((project, version));
+            }
+
+            let mut results = Vec::new();
+            for (mut project, version) in members {
+                say!(
+                    out,
+                    "{name}: Godot Engine v{engine_version}",
+                    name = &project.name,
+                    engine_version = project.config.version
+                );
+                results.push(upgrade(&mut project, version, out).await?);
+            }
+
+            Ok(json!({
+                "workspace": workspace.root,
+                "members": results,
+            }))
+        }
+        Commands::Upgrade {
+            path,
+            workspace: false,
+        } => {
+            // a project given by path is created when it does not exist yet
+            let dirs = match path.or(project_dir) {
+                Some(path) => dirs::init_new(Some(path)).await?,
+                None => dirs::init(None).await?,
+            };
+
+            match project::Project::load(&dirs) {
+                Ok(mut project) => {
+                    say!(
+                        out,
+                        "Found existing project: {name}, Godot Engine v{engine_version}!",
+                        name = &project.name,
+                        engine_version = project.config.version
+                    );
+                    let version =
+                        upgrade_version(&project, &settings()?, &mut HashMap::new()).await?;
+                    upgrade(&mut project, version, out).await
+                }
+                Err(gdm::Error::ProjectNotFound { .. }) => {
+                    let settings = settings()?;
+                    let project = ProjectConfiguration::init(
+                        &dirs,
+                        &settings,
+                        ConfigFormat::default(),
+                        &settings.downloader()?,
+                    )
+                    .await?;
+                    let inferred = report_inferred(&project, out)?;
+                    say!(
+                        out,
+                        "Successfully initialized new project: {name}, Godot Engine v{engine_version}",
+                        name = &project.name,
+                        engine_version = project.config.version
+                    );
+
+                    Ok(json!({
+                        "project": project.name,
+                        "previous_version": null,
+                        "version": project.config.version.to_string(),
+                        "upgraded": false,
+                        "initialized": true,
+                        "inferred": inferred,
+                    }))
+                }
+                Err(error) => Err(error.into()),
+            }
+        }
+        Commands::Set { version, path } => {
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let mut project = load_project(&dirs)?;
+            project.config.version = EngineVersion::from_string(version);
+            project.save()?;
+            say!(
+                out,
+                "Successfully set Godot Engine version to {engine_version}",
+                engine_version = project.config.version
+            );
+
+            Ok(json!({
+                "project": project.name,
+                "version": project.config.version.to_string(),
+            }))
+        }
+        Commands::Init { path, mono, format } => {
+            let dirs = dirs::init_new(path.or(project_dir)).await?;
+
+            match project::Project::load(&dirs) {
+                Ok(project) => {
+                    say!(
+                        out,
+                        "Found existing project: {name}, Godot Engine v{engine_version}, aborting!",
+                        name = &project.name,
+                        engine_version = project.config.version
+                    );
+
+                    Ok(json!({
+                        "project": project.name,
+                        "version": project.config.version.to_string(),
+                        "mono": project.config.mono,
+                        "initialized": false,
+                    }))
+                }
+                Err(gdm::Error::ProjectNotFound { .. }) => {
+                    let mut settings = settings()?;
+                    settings.mono |= mono;
+                    let project = ProjectConfiguration::init(
+                        &dirs,
+                        &settings,
+                        format,
+                        &settings.downloader()?,
+                    )
+                    .await?;
+                    let inferred = report_inferred(&project, out)?;
+                    say!(
+                        out,
+                        "Successfully initialized new project: {name}, Godot Engine v{engine_version}",
+                        name = &project.name,
+                        engine_version = project.config.version
+                    );
+
+                    Ok(json!({
+                        "project": project.name,
+                        "version": project.config.version.to_string(),
+                        "mono": project.config.mono,
+                        "initialized": true,
+                        "inferred": inferred,
+                    }))
+                }
+                Err(error) => Err(error.into()),
+            }
+        }
+        Commands::Run {
+            target,
+            path,
+            console,
+            editor,
+            game,
+            headless,
+            project_manager,
+            script,
+            attach,
+            detach,
+            log,
+            args,
+        } => {
+            // a lone existing directory keeps `gdm run <path>` working, unless a run configuration
+            // of the same name exists
+            let (path, run_name) = match (target, path) {
+                (Some(target), None)
+                    if Path::new(&target).is_dir()
+                        && !has_run(project_dir.clone(), &target).await =>
+                {
+                    (Some(PathBuf::from(target)), None)
+                }
+                (target, path) => (path, target),
+            };
+
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let project = load_project(&dirs)?;
+
+            let mut options = match &run_name {
+                Some(name) => match project.config.runs.get(name) {
+                    Some(run) => run.launch_options()?,
+                    None => fail!(
+                        Failure::NotFound,
+                        "Unknown run configuration \"{name}\", available configurations: {}",
+                        project
+                            .config
+                            .runs
+                            .keys()
+                            .map(String::as_str)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                },
+                None => LaunchOptions::default(),
+            };
+
+            let mode = if let Some(scene) = game {
+                Some(LaunchMode::Game { scene })
+            } else if headless {
+                Some(LaunchMode::Headless)
+            } else if project_manager {
+                Some(LaunchMode::ProjectManager)
+            } else if let Some(script) = script {
+                Some(LaunchMode::Script { script })
+            } else if editor {
+                Some(LaunchMode::Editor)
+            } else {
+                None
+            };
+
+            if let Some(mode) = mode {
+                options.attached = mode.attaches_by_default();
+                options.mode = mode;
+            } else if run_name.is_none() {
+                options.attached = options.mode.attaches_by_default();
+            }
+            if attach || detach {
+                options.attached = attach;
+            }
+            options.console |= console;
+            options.log |= log;
+            options.args.extend(args);
+
+            let installed = ensure_installed(&project, out).await?;
+            let status = project
+                .run(&options, &*out.progress)
+                .await
+                .or_fail(Failure::Engine)?;
+
+            if let Some(status) = status
+                && !status.success()
+            {
+                return Err(CliError::new(
+                    Failure::Engine,
+                    format!("Godot Engine {}", process::describe(&status)),
+                )
+                .with_exit_code(process::exit_code(&status)));
+            }
+
+            Ok(json!({
+                "project": project.name,
+                "version": project.config.version.to_string(),
+                "engine_path": project.engine_path(options.console),
+                "engine_installed": installed,
+                "attached": options.attached,
+                "exit_code": status.map(|status| process::exit_code(&status)),
+            }))
+        }
+        Commands::Which { path, console } => {
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let project = load_project(&dirs)?;
+            let engine_path = project.engine_path(console);
+            let installed = project.is_engine_installed();
+
+            say!(out, "{}", engine_path.display());
+            if !installed && !out.json {
+                eprintln!(
+                    "Godot Engine v{} is not installed yet, it will be downloaded by `gdm run`",
+                    project.config.version
+                );
+            }
+
+            Ok(json!({
+                "engine_path": engine_path,
+                "installed": installed,
+            }))
+        }
+        Commands::Info { path } => {
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let project = load_project(&dirs)?;
+            let info = project.info(settings()?.upgrade)?;
+
+            let yes_no = |value: bool| if value { "yes" } else { "no" };
+            say!(out, "Project:      {}", info.name);
+            say!(out, "Config:       {}", info.config_path.display());
+            say!(
+                out,
+                "Constraint:   {} (upgrade: {})",
+                info.constraint.version,
+                info.constraint.upgrade
+            );
+            say!(out, "Version:      {}", info.version);
+            say!(out, "Mono:         {}", yes_no(info.mono));
+            say!(out, "Source:       {}", info.download_source);
+            say!(out, "Installed:    {}", yes_no(info.installed));
+            say!(out, "Install path: {}", info.install_path.display());
+            say!(out, "Disk usage:   {}", HumanBytes(info.disk_usage));
+
+            Ok(serde_json::to_value(info)?)
+        }
+        Commands::Exec { path, command } => {
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let project = load_project(&dirs)?;
+            ensure_installed(&project, out).await?;
+
+            let (program, args) = command
+                .split_first()
+                .expect("clap requires at least one argument");
+            let child = tokio::process::Command::new(program)
+                .args(args)
+                .envs(project.engine_env())
+                .stdout(process::stdout(&*out.progress))
+                .spawn()
+                .or_fail(Failure::NotFound)?;
+
+            let status = process::wait(child).await?;
+            if !status.success() {
+                return Err(CliError::new(
+                    Failure::General,
+                    format!("{program} {}", process::describe(&status)),
+                )
+                .with_exit_code(process::exit_code(&status)));
+            }
+
+            Ok(json!({ "exit_code": 0 }))
+        }
+        Commands::Task { name, path } => {
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let project = load_project(&dirs)?;
+
+            let Some(name) = name else {
+                for (name, task) in &project.config.tasks {
+                    let definition = task.definition();
+                    match definition.command {
+                        Some(command) => say!(out, "{name}: {command}"),
+                        None => say!(out, "{name}: {}", definition.depends.join(", ")),
+                    }
+                }
+                return Ok(json!({ "tasks": project.config.tasks }));
+            };
+
+            if !project.config.tasks.contains_key(&name) {
+                fail!(
+                    Failure::NotFound,
+                    "Unknown task \"{name}\", available tasks: {}",
+                    project
+                        .config
+                        .tasks
+                        .keys()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+
+            ensure_installed(&project, out).await?;
+            let ran = tasks::run(&project, &name, &*out.progress).await?;
+
+            Ok(json!({ "task": name, "ran": ran }))
+        }
+        Commands::Logs {
+            path,
+            follow,
+            run,
+            list,
+        } => {
+            // the followed output is raw text that never ends, not a JSON document
+            if follow && out.json {
+                fail!(Failure::General, "--follow cannot be combined with --json");
+            }
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let project = load_project(&dirs)?;
+            let available = logs::list(&project)?;
+
+            if list {
+                for (index, log) in available.iter().enumerate() {
+                    say!(out, "{}: {}", index + 1, log.display());
+                }
+                return Ok(json!({ "logs": available }));
+            }
+
+            let Some(log) = run.checked_sub(1).and_then(|index| available.get(index)) else {
+                fail!(
+                    Failure::NotFound,
+                    "No log for run {run}, {} runs were logged, use `gdm run --log` to capture engine output",
+                    available.len()
+                );
+            };
+
+            if follow {
+                logs::follow(log).await?;
+            }
+
+            let content = fs::read(log)?;
+            if !out.json {
+                io::stdout().write_all(&content)?;
+            }
+
+            Ok(json!({
+                "path": log,
+                "content": String::from_utf8_lossy(&content),
+            }))
+        }
+        Commands::Export {
+            presets,
+            release: _,
+            debug,
+            out: out_dir,
+            list,
+            path,
+        } => {
+            let dirs = dirs::init(path.or(project_dir)).await?;
+            let project = load_project(&dirs)?;
+            let available = export::load_presets(&project).or_fail(Failure::NotFound)?;
+
+            if list {
+                for preset in &available {
+                    let export_path = if preset.is_valid() {
+                        preset.export_path.as_str()
+                    } else {
+                        "<no export path>"
+                    };
+                    say!(
+                        out,
+                        "{} ({}): {}",
+                        preset.name,
+                        preset.platform,
+                        export_path
+                    );
+                }
+                return Ok(json!({ "presets": available }));
+            }
+
+            let selected = if presets.is_empty() {
+                available
+            } else {
+                let mut selected = Vec::new();
+                for name in &presets {
+                    match available.iter().find(|preset| &preset.name == name) {
+                        Some(preset) => selected.push(preset.clone()),
+                        None => fail!(
+                            Failure::NotFound,
+                            "Unknown export preset \"{name}\", available presets: {}",
+                            available
+                                .iter()
+                                .map(|preset| preset.name.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    }
+                }
+                selected
+            };
+
+            if selected.is_empty() {
+                fail!(
+                    Failure::NotFound,
+                    "No export presets defined in export_presets.cfg"
+                );
+            }
+
+            ensure_installed(&project, out).await?;
+            export::ensure_templates_installed(&project, &downloader()?, &*out.progress).await?;
+
+            let mode = if debug {
+                ExportMode::Debug
+            } else {
+                ExportMode::Release
+            };
+
+            let mut results = Vec::new();
+            for preset in &selected {
+                say!(out, "Exporting {}...", preset.name);
+                match export::export(&project, preset, mode, out_dir.as_deref()) {
+                    Ok(result) => {
+                        if result.success {
+                            say!(
+                                out,
+                                "Exported {} to {}",
+                                result.preset,
+                                result.output.display()
+                            );
+                        } else {
+                            say!(out, "Failed to export {}", result.preset);
+                        }
+                        results.push(json!(result));
+                    }
+                    Err(e) => {
+                        say!(out, "Failed to export {}: {e}", preset.name);
+                        results.push(json!({
+                            "preset": preset.name,
+                            "output": null,
+                            "success": false,
+                            "error": e.to_string(),
+                        }));
+                    }
+                }
+            }
+
+            let failed = results
+                .iter()
+                .filter(|result| result["success"] != true)
+                .count();
+            let data = json!({
+                "mode": if debug { "debug" } else { "release" },
+                "results": results,
+            });
+
+            if failed > 0 {
+                return Err(CliError::new(
+                    Failure::Engine,
+                    format!("{failed} of {} presets failed to export", selected.len()),
+                )
+                .with_details(data));
+            }
+
+            say!(out, "Done!");
+            Ok(data)
+        }
+        Commands::Engine {
+            command: EngineCommands::Install { path, workspace },
+        } => {
+            let projects = match workspace {
+                true => find_workspace(path.or(project_dir))?
+                    .member_dirs()
+                    .into_iter()
+                    .map(Some)
+                    .collect(),
+                false => vec![path.or(project_dir)],
+            };
+
+            let mut results = Vec::new();
+            for project_path in projects {
+                let project = load_project(&dirs::init(project_path).await?)?;
+                // members sharing an engine install it once, the others find it installed
+                let downloaded = ensure_installed(&project, out).await?;
+                if !downloaded {
+                    say!(
+                        out,
+                        "{name}: Godot Engine v{engine_version} is already installed",
+                        name = &project.name,
+                        engine_version = project.config.version
+                    );
+                }
+                results.push(json!({
+                    "project": project.name,
+                    "version": project.config.version.to_string(),
+                    "path": project.engine_dir(),
+                    "downloaded": downloaded,
+                }));
+            }
+
+            Ok(json!({ "projects": results }))
+        }
+        Commands::Config { global, command } => {
+            config::run(command, global, project_dir, out).await
+        }
+        Commands::Clean => {
+            say!(out, "Deleting all engine versions and cache...");
+            let dirs = dirs::init_no_project().await?;
+
+            let mut removed = Vec::new();
+            for path in [dirs.cache_dir, dirs.engines_install_dir] {
+                if path.is_dir() {
+                    fs::remove_dir_all(&path)?;
+                    removed.push(path);
+                }
+            }
+
+            say!(out, "Done!");
+            Ok(json!({ "removed": removed }))
+        } // Commands::Engine { command } => match command {
+          //     EngineCommands::Help => println!("Engine Help"),
+          //     EngineCommands::Upgrade => println!("Engine Upgrade"),
+          //     EngineCommands::Set { version } => println!("Engine Set: {}", version),
+          // },
+    }
+    // println!("{:?}", args);
+}
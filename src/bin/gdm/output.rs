@@ -0,0 +1,236 @@
+use core::fmt;
+use gdm::util::download::DownloadError;
+use gdm::util::progress::Progress;
+use serde_json::{Value, json};
+use std::io;
+
+/// Failure classes reported through gdm's exit code, see the README for the documented values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Failure {
+    General,
+    NotFound,
+    Network,
+    Checksum,
+    Engine,
+}
+
+impl Failure {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Failure::General => 1,
+            // 2 is used by clap for invalid arguments
+            Failure::NotFound => 3,
+            Failure::Network => 4,
+            Failure::Checksum => 5,
+            Failure::Engine => 6,
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Failure::General => "error",
+            Failure::NotFound => "not_found",
+            Failure::Network => "network",
+            Failure::Checksum => "checksum",
+            Failure::Engine => "engine_failed",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CliError {
+    pub failure: Failure,
+    pub message: String,
+    /// Overrides the failure's exit code, used to pass through the exit code of the engine.
+    pub exit_code: Option<i32>,
+    /// Partial results that are still useful to scripts, e.g. which presets did export.
+    pub details: Box<Value>,
+    /// Suggests how to fix the problem, printed below the message.
+    pub hint: Option<String>,
+}
+
+impl CliError {
+    pub fn new(failure: Failure, message: impl fmt::Display) -> CliError {
+        CliError {
+            failure,
+            message: message.to_string(),
+            exit_code: None,
+            details: Box::new(Value::Null),
+            hint: None,
+        }
+    }
+
+    pub fn with_exit_code(mut self, exit_code: i32) -> CliError {
+        self.exit_code = Some(exit_code);
+        self
+    }
+
+    pub fn with_details(mut self, details: Value) -> CliError {
+        self.details = Box::new(details);
+        self
+    }
+}
+
+impl From<rootcause::Report> for CliError {
+    fn from(report: rootcause::Report) -> Self {
+        CliError::new(Failure::General, report)
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        CliError::new(Failure::General, error)
+    }
+}
+
+impl From<DownloadError> for CliError {
+    fn from(error: DownloadError) -> Self {
+        gdm::Error::from(error).into()
+    }
+}
+
+impl From<gdm::Error> for CliError {
+    fn from(error: gdm::Error) -> Self {
+        let failure = match &error {
+            gdm::Error::ProjectNotFound { .. } | gdm::Error::VersionNotFound { .. } => {
+                Failure::NotFound
+            }
+            gdm::Error::Network { .. } => Failure::Network,
+            gdm::Error::ChecksumMismatch { .. } => Failure::Checksum,
+            _ => Failure::General,
+        };
+        let hint = match &error {
+            gdm::Error::ProjectNotFound { .. } => {
+                Some(
+                    "use `gdm init` to create a project, gdm uses the nearest directory from the current one up \
+                     that holds a project.godot, gdm.toml or project.json"
+                        .to_string(),
+                )
+            }
+            gdm::Error::ProjectExists { .. } => {
+                Some("use `gdm upgrade` or `gdm set` to change its engine version".to_string())
+            }
+            gdm::Error::ConfigParse { path, .. }
+                if gdm::settings::Settings::path(gdm::util::dirs::user_home().as_deref())
+                    .is_ok_and(|settings| settings == *path) =>
+            {
+                Some("fix the setting with `gdm config --global set` or `unset`".to_string())
+            }
+            gdm::Error::ConfigParse { .. } => {
+                Some("fix the file or remove it and run `gdm init` again".to_string())
+            }
+            gdm::Error::Extends { .. } => {
+                Some("check the `extends` key of the configuration".to_string())
+            }
+            gdm::Error::ExtendsNotCached { .. } => {
+                Some("run `gdm config fetch` to download it".to_string())
+            }
+            gdm::Error::GdmTooOld { .. } | gdm::Error::SchemaTooNew { .. } => Some(format!(
+                "update gdm to continue, see {}",
+                gdm::built_info::PKG_HOMEPAGE
+            )),
+            gdm::Error::VersionNotFound { .. } => Some(
+                "check the version against https://github.com/godotengine/godot/releases, e.g. 4.3-stable"
+                    .to_string(),
+            ),
+            gdm::Error::Network { .. } => {
+                Some("check your internet connection and try again".to_string())
+            }
+            gdm::Error::ChecksumMismatch { .. } => Some(
+                "the corrupted download was deleted, run the command again to download it again"
+                    .to_string(),
+            ),
+            _ => None,
+        };
+
+        CliError {
+            hint,
+            ..CliError::new(failure, error)
+        }
+    }
+}
+
+impl From<serde_json::Error> for CliError {
+    fn from(error: serde_json::Error) -> Self {
+        CliError::new(Failure::General, error)
+    }
+}
+
+pub trait Classify<T> {
+    /// Reports an error of this result as the given failure class.
+    fn or_fail(self, failure: Failure) -> Result<T, CliError>;
+}
+
+impl<T, E: Into<CliError>> Classify<T> for Result<T, E> {
+    fn or_fail(self, failure: Failure) -> Result<T, CliError> {
+        self.map_err(|error| {
+            let mut error = error.into();
+            error.failure = failure;
+            error
+        })
+    }
+}
+
+macro_rules! fail {
+    ($failure:expr, $($arg:tt)*) => {
+        return Err($crate::output::CliError::new($failure, format!($($arg)*)))
+    };
+}
+
+/// Prints a message for humans, nothing is printed with `--json`.
+macro_rules! say {
+    ($output:expr, $($arg:tt)*) => {
+        $output.say(format_args!($($arg)*))
+    };
+}
+
+pub(crate) use {fail, say};
+
+pub struct Output {
+    pub json: bool,
+    pub progress: Box<dyn Progress>,
+}
+
+impl Output {
+    pub fn say(&self, message: fmt::Arguments) {
+        if !self.json {
+            println!("{message}");
+        }
+    }
+
+    /// Prints the command's result and returns the process exit code.
+    pub fn finish(&self, result: Result<Value, CliError>) -> i32 {
+        match result {
+            Ok(data) => {
+                if self.json {
+                    println!("{}", json!({ "ok": true, "data": data }));
+                }
+                0
+            }
+            Err(error) => {
+                let exit_code = error.exit_code.unwrap_or(error.failure.exit_code());
+                if self.json {
+                    println!(
+                        "{}",
+                        json!({
+                            "ok": false,
+                            "error": {
+                                "code": error.failure.code(),
+                                "message": error.message,
+                                "details": error.details,
+                                "hint": error.hint,
+                            },
+                            "exit_code": exit_code,
+                        })
+                    );
+                } else {
+                    eprintln!("Error: {}", error.message);
+                    if let Some(hint) = &error.hint {
+                        eprintln!("Hint: {hint}");
+                    }
+                }
+                exit_code
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use gdm::util::progress::{Event, Progress};
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use std::io::{self, IsTerminal};
+use std::sync::Mutex;
+
+/// Picks progress bars for interactive terminals and plain lines for everything else, both on stderr.
+pub fn for_terminal(json: bool) -> Box<dyn Progress> {
+    if !json && io::stdout().is_terminal() {
+        Box::new(BarProgress::default())
+    } else {
+        Box::new(LineProgress { json })
+    }
+}
+
+/// Status line for events that look the same on terminals and in logs.
+fn describe(event: &Event) -> Option<String> {
+    match event {
+        Event::Resolve { engine, .. } => {
+            Some(format!("Could not find {engine} locally, downloading..."))
+        }
+        Event::Verify { file } => Some(format!(
+            "Verifying checksum of {}...",
+            file.file_name().unwrap_or_default().to_string_lossy()
+        )),
+        Event::Extract { .. } => Some("Extracting archive...".to_string()),
+        Event::Done { name, .. } => Some(format!("Successfully installed {name}")),
+        Event::Command { label, command } => Some(format!("> {label}: {command}")),
+        Event::Message(message) => Some(message.clone()),
+        Event::DownloadStart { .. }
+        | Event::DownloadProgress { .. }
+        | Event::DownloadFinish { .. } => None,
+    }
+}
+
+#[derive(Default)]
+pub struct BarProgress {
+    bar: Mutex<Option<ProgressBar>>,
+}
+
+impl Progress for BarProgress {
+    fn event(&self, event: Event) {
+        let mut bar = self.bar.lock().expect("progress bar lock poisoned");
+
+        match event {
+            Event::DownloadStart { url, total } => {
+                let (new_bar, template) = match total {
+                    Some(total) => (
+                        ProgressBar::new(total),
+                        "{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})",
+                    ),
+                    None => (
+                        ProgressBar::no_length(),
+                        "{msg}\n{spinner:.green} [{elapsed_precise}] {bytes} ({bytes_per_sec})",
+                    ),
+                };
+                new_bar.set_style(
+                    ProgressStyle::default_bar()
+                        .template(template)
+                        .unwrap()
+                        .progress_chars("#>-"),
+                );
+                new_bar.set_message(format!("Downloading {url}"));
+                *bar = Some(new_bar);
+            }
+            Event::DownloadProgress { downloaded, .. } => {
+                if let Some(bar) = bar.as_ref() {
+                    bar.set_position(downloaded);
+                }
+            }
+            Event::DownloadFinish { downloaded } => {
+                if let Some(bar) = bar.take() {
+                    bar.finish_with_message(format!("Downloaded {}", HumanBytes(downloaded)));
+                }
+            }
+            event => {
+                if let Some(line) = describe(&event) {
+                    match bar.as_ref() {
+                        Some(bar) => bar.println(line),
+                        None => eprintln!("{line}"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Logs one line per step, for CI logs and other non-interactive output.
+pub struct LineProgress {
+    /// Keeps stdout to the JSON document, the output of child processes goes to stderr.
+    pub json: bool,
+}
+
+impl Progress for LineProgress {
+    fn event(&self, event: Event) {
+        match event {
+            Event::DownloadStart { url, total } => match total {
+                Some(total) => eprintln!("Downloading {url} ({})", HumanBytes(total)),
+                None => eprintln!("Downloading {url}"),
+            },
+            Event::DownloadProgress { .. } => {}
+            Event::DownloadFinish { downloaded } => {
+                eprintln!("Downloaded {}", HumanBytes(downloaded))
+            }
+            event => {
+                if let Some(line) = describe(&event) {
+                    eprintln!("{line}");
+                }
+            }
+        }
+    }
+
+    fn redirect_output(&self) -> bool {
+        self.json
+    }
+}
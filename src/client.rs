@@ -0,0 +1,212 @@
+use crate::Error;
+use crate::project::config::{EngineDownloadSource, ProjectConfiguration};
+use crate::project::engine::EngineVersion;
+use crate::project::launch::LaunchOptions;
+use crate::project::{Project, versions};
+use crate::settings::Settings;
+use crate::util::dirs::{self, Dirs};
+use crate::util::download::Downloader;
+use crate::util::progress::{Progress, Silent};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::sync::Arc;
+use std::{fs, io};
+
+/// Entry point for embedding gdm in other tools, e.g. editor plugins or CI runners.
+///
+/// ```no_run
+/// # async fn example() -> gdm::Result<()> {
+/// let gdm = gdm::Gdm::builder().offline(true).build()?;
+/// let project = gdm.open_project("my-game")?;
+/// gdm.launch(&project, &Default::default()).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Gdm {
+    dirs: Dirs,
+    source: EngineDownloadSource,
+    downloader: Downloader,
+    progress: Arc<dyn Progress>,
+}
+
+#[derive(Default)]
+pub struct GdmBuilder {
+    home: Option<PathBuf>,
+    source: Option<EngineDownloadSource>,
+    client: Option<reqwest::Client>,
+    progress: Option<Arc<dyn Progress>>,
+    settings: Option<Settings>,
+    offline: bool,
+}
+
+/// An engine found in gdm's install directory.
+#[derive(Serialize, Debug)]
+pub struct InstalledEngine {
+    pub name: String,
+    pub version: EngineVersion,
+    pub mono: bool,
+    pub path: PathBuf,
+}
+
+impl GdmBuilder {
+    /// Keeps engines, downloads, caches and settings below `home` instead of the platform's directories.
+    pub fn home(mut self, home: impl Into<PathBuf>) -> GdmBuilder {
+        self.home = Some(home.into());
+        self
+    }
+
+    /// Uses these settings instead of the user's settings file.
+    pub fn settings(mut self, settings: Settings) -> GdmBuilder {
+        self.settings = Some(settings);
+        self
+    }
+
+    /// Where `resolve` looks up the latest version, the settings' download source by default.
+    pub fn source(mut self, source: EngineDownloadSource) -> GdmBuilder {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn http_client(mut self, client: reqwest::Client) -> GdmBuilder {
+        self.client = Some(client);
+        self
+    }
+
+    /// Receives the events of installs and launches, nothing is reported by default.
+    pub fn progress(mut self, progress: impl Progress + 'static) -> GdmBuilder {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Fails operations that need the network instead of downloading anything.
+    pub fn offline(mut self, offline: bool) -> GdmBuilder {
+        self.offline = offline;
+        self
+    }
+
+    pub fn build(self) -> crate::Result<Gdm> {
+        let settings = match self.settings {
+            Some(settings) => settings,
+            None => Settings::load(self.home.as_deref())?,
+        };
+        let downloader = match self.client {
+            Some(client) => settings.configure(Downloader::with_client(client)),
+            None => settings.downloader()?,
+        };
+
+        Ok(Gdm {
+            dirs: dirs::global(self.home, &settings)?,
+            source: self.source.unwrap_or(settings.download_source),
+            downloader: downloader.offline(self.offline),
+            progress: self.progress.unwrap_or_else(|| Arc::new(Silent)),
+        })
+    }
+}
+
+impl Gdm {
+    pub fn builder() -> GdmBuilder {
+        GdmBuilder::default()
+    }
+
+    pub fn dirs(&self) -> &Dirs {
+        &self.dirs
+    }
+
+    pub fn downloader(&self) -> &Downloader {
+        &self.downloader
+    }
+
+    /// Turns a version like `4.3-stable` or `latest` into a concrete engine version.
+    pub async fn resolve(&self, version: &str) -> crate::Result<EngineVersion> {
+        if version == "latest" {
+            return self.source.get_latest_version(&self.downloader).await;
+        }
+        Ok(EngineVersion::from_string(version.to_string()))
+    }
+
+    /// Installs an engine version outside of any project, does nothing if it is installed already.
+    pub async fn install(
+        &self,
+        version: EngineVersion,
+        mono: bool,
+    ) -> crate::Result<InstalledEngine> {
+        let config = ProjectConfiguration::new(version, self.source.clone(), mono).await?;
+        let project = Project {
+            name: String::new(),
+            config,
+            dirs: self.dirs.clone(),
+            warnings: Vec::new(),
+            document: Default::default(),
+            base: None,
+            loaded: serde_json::Value::Null,
+        };
+
+        versions::ensure_version_installed(&project, &self.downloader, &*self.progress).await?;
+
+        Ok(InstalledEngine {
+            name: project.config.get_engine_name(),
+            path: project.engine_dir(),
+            version: project.config.version,
+            mono,
+        })
+    }
+
+    /// Engines in the install directory, sorted by name.
+    pub fn list_installed(&self) -> io::Result<Vec<InstalledEngine>> {
+        let dir = &self.dirs.engines_install_dir;
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut engines = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // Godot_v<version>_<os>, where the os part starts with mono_ for .NET builds
+            let Some((version, os)) = name
+                .strip_prefix("Godot_v")
+                .and_then(|rest| rest.split_once('_'))
+            else {
+                continue;
+            };
+
+            engines.push(InstalledEngine {
+                version: EngineVersion::from_string(version.to_string()),
+                mono: os.starts_with("mono_"),
+                path: entry.path(),
+                name,
+            });
+        }
+
+        engines.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(engines)
+    }
+
+    pub fn open_project(&self, path: impl AsRef<Path>) -> crate::Result<Project> {
+        if !path.as_ref().is_dir() {
+            return Err(Error::ProjectNotFound {
+                path: path.as_ref().to_path_buf(),
+            });
+        }
+        let dirs = self
+            .dirs
+            .clone()
+            .with_project(path.as_ref().to_path_buf())?;
+        Project::load(&dirs)
+    }
+
+    /// Installs the project's engine if needed and launches it, see [`Project::run`].
+    pub async fn launch(
+        &self,
+        project: &Project,
+        options: &LaunchOptions,
+    ) -> crate::Result<Option<ExitStatus>> {
+        versions::ensure_version_installed(project, &self.downloader, &*self.progress).await?;
+        project.run(options, &*self.progress).await
+    }
+}
@@ -0,0 +1,77 @@
+use crate::project::diagnostics::Diagnostic;
+use crate::util::archive::ExtractError;
+use crate::util::download::DownloadError;
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Errors of gdm's public operations that callers may want to handle individually.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("No gdm configuration found in {}", path.display())]
+    ProjectNotFound { path: PathBuf },
+    #[error("A project already exists in {}", path.display())]
+    ProjectExists { path: PathBuf },
+    #[error("Invalid {}: {diagnostic}", path.display())]
+    ConfigParse {
+        path: PathBuf,
+        diagnostic: Box<Diagnostic>,
+    },
+    #[error("This project requires gdm {required} or newer, but this is gdm {current}")]
+    GdmTooOld { required: String, current: String },
+    #[error(
+        "The configuration uses schema {schema}, but this gdm only understands schemas up to {supported}"
+    )]
+    SchemaTooNew { schema: u64, supported: u32 },
+    #[error("Godot Engine is not available for {0}")]
+    UnsupportedPlatform(String),
+    #[error("Unknown download source `{0}`, expected `github`")]
+    UnknownDownloadSource(String),
+    #[error("Could not read `{extends}`, which the configuration extends: {reason}")]
+    Extends { extends: String, reason: String },
+    #[error("{url}, which the configuration extends, has not been downloaded yet")]
+    ExtendsNotCached { url: String },
+    #[error("Invalid setting `{key}`: {reason}")]
+    InvalidSetting { key: String, reason: String },
+    #[error("Godot Engine {version} could not be found, no {file} was published for it")]
+    VersionNotFound { version: String, file: String },
+    #[error("Could not download {url}: {reason}")]
+    Network { url: String, reason: String },
+    #[error("Checksum of {file} does not match, expected {expected} but got {actual}")]
+    ChecksumMismatch {
+        file: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Could not extract {}: {source}", archive.display())]
+    Extract {
+        archive: PathBuf,
+        source: ExtractError,
+    },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// Any error without a variant of its own, e.g. a failing hook.
+    #[error("{0}")]
+    Other(rootcause::Report),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl From<rootcause::Report> for Error {
+    fn from(report: rootcause::Report) -> Self {
+        Error::Other(report)
+    }
+}
+
+impl From<DownloadError> for Error {
+    fn from(error: DownloadError) -> Self {
+        match error {
+            DownloadError::IoError(error) => Error::Io(error),
+            error => Error::Network {
+                url: error.url().unwrap_or_default().to_string(),
+                reason: error.to_string(),
+            },
+        }
+    }
+}
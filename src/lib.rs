@@ -1,5 +1,12 @@
+mod client;
+mod error;
 pub mod project;
+pub mod settings;
 pub mod util;
+pub mod workspace;
+
+pub use client::{Gdm, GdmBuilder, InstalledEngine};
+pub use error::{Error, Result};
 
 pub mod built_info {
     // The file has been placed there by the build script.
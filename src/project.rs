@@ -1,50 +1,209 @@
-use crate::util::dirs::Dirs;
-use rootcause::bail;
-use std::{fs, path::PathBuf};
+use crate::Error;
+use crate::util::atomic;
+use crate::util::dirs::{self, Dirs};
+use crate::util::os::OS;
+use crate::util::process;
+use crate::util::progress::{Event, Progress};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::process::{ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::{fs, path::Path, path::PathBuf};
 
 pub mod config;
+pub mod diagnostics;
+mod document;
 pub mod engine;
+pub mod export;
+pub mod extends;
+pub mod format;
+pub mod hooks;
+pub mod inspect;
+pub mod launch;
+pub mod logs;
+pub mod migrations;
+pub mod tasks;
 pub mod versions;
 
+use config::UpgradePolicy;
+use document::Document;
+
 pub struct Project {
     pub name: String,
     pub config: config::ProjectConfiguration,
     pub dirs: Dirs,
+    /// Problems in the configuration that did not prevent loading it, e.g. unknown keys.
+    pub warnings: Vec<diagnostics::Diagnostic>,
+    pub(crate) document: Document,
+    pub(crate) base: Option<extends::Base>,
+    /// The configuration as it was loaded, saving compares against it to write only what changed.
+    pub(crate) loaded: serde_json::Value,
+}
+
+/// The project's directory name, used when none is configured.
+pub(crate) fn project_name(path: &Path) -> String {
+    match path.file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => path.display().to_string(),
+    }
+}
+
+/// Summary of a project and the state of its engine, as shown by `gdm info`.
+#[derive(Serialize, Debug)]
+pub struct ProjectInfo {
+    pub name: String,
+    pub config_path: PathBuf,
+    pub constraint: VersionConstraint,
+    /// The release the constraint resolves to, the one installed in `install_path`.
+    pub version: String,
+    pub mono: bool,
+    pub download_source: String,
+    pub installed: bool,
+    pub install_path: PathBuf,
+    pub engine_path: PathBuf,
+    /// Size of the engine installation in bytes, 0 when it is not installed.
+    pub disk_usage: u64,
+}
+
+/// Which engine versions the configuration allows.
+#[derive(Serialize, Debug)]
+pub struct VersionConstraint {
+    /// The version as the configuration writes it, e.g. `4.3` in a `.godot-version`.
+    pub version: String,
+    /// Which releases `gdm upgrade` moves the project to.
+    pub upgrade: UpgradePolicy,
 }
 
 impl Project {
-    pub fn load(dirs: &Dirs) -> rootcause::Result<Project> {
+    pub fn load(dirs: &Dirs) -> crate::Result<Project> {
         let project_absolute_path = dunce::canonicalize(&dirs.project_dir)?;
 
-        let config_path = project_absolute_path.join("project.json");
-
-        if !config_path.exists() {
-            bail!("Project does not exist") // TODO error handling
-        }
+        let formats = format::ConfigFormat::detect(&project_absolute_path);
+        let Some(&format) = formats.first() else {
+            return Err(Error::ProjectNotFound {
+                path: project_absolute_path,
+            });
+        };
+        let config_path = project_absolute_path.join(format.file_name());
 
-        let project_name = project_absolute_path
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
+        let text = fs::read_to_string(&config_path)?;
+        let base = extends::resolve(&text, format, &project_absolute_path, dirs)?;
+        let (config, mut warnings) =
+            diagnostics::check(&text, format, base.as_ref().map(|base| &base.value))?;
+        let Some(config) = config else {
+            return Err(Error::ConfigParse {
+                path: config_path,
+                diagnostic: Box::new(
+                    warnings
+                        .pop()
+                        .expect("a failed check reports its error last"),
+                ),
+            });
+        };
+        OS::current().get_os_string(config.mono)?;
 
-        let config = serde_json::from_str(&fs::read_to_string(config_path)?)?;
+        for &ignored in &formats[1..] {
+            let message = if ignored.is_version_pin() {
+                // pins kept for other tools only matter when they disagree
+                let pinned = Document::load(ignored, &project_absolute_path)?.version_pin();
+                let version = format::version_pin(&config.version.to_string(), config.mono);
+                if pinned.as_ref() == Some(&version) {
+                    continue;
+                }
+                let pinned = pinned.unwrap_or_default();
+                format!(
+                    "{} pins {pinned}, but {} uses {version}, `gdm config export --format {ignored}` updates it",
+                    ignored.file_name(),
+                    format.file_name()
+                )
+            } else {
+                format!(
+                    "The configuration in {} is ignored, {} takes precedence",
+                    ignored.file_name(),
+                    format.file_name()
+                )
+            };
+            warnings.push(diagnostics::Diagnostic {
+                severity: diagnostics::Severity::Warning,
+                message,
+                json_path: ".".to_string(),
+                line: None,
+                column: None,
+                suggestion: None,
+            });
+        }
 
         Ok(Project {
-            name: project_name,
+            name: project_name(&project_absolute_path),
             dirs: dirs.clone(),
+            loaded: serde_json::to_value(&config).expect("the configuration serializes to JSON"),
             config,
+            warnings,
+            document: Document::parse(format, &text),
+            base,
         })
     }
 
-    pub fn save(&self) -> rootcause::Result<()> {
-        let config_path = self.path().join("project.json");
+    /// Writes the configuration, leaving the file untouched when the configuration did not change.
+    ///
+    /// Only what differs from the configuration the project extends is written.
+    pub fn save(&mut self) -> crate::Result<()> {
+        let path = self.config_path();
+        let rendered = self.document.render(self.own_config());
+        let Some(text) = rendered.map_err(|diagnostic| Error::ConfigParse {
+            path: path.clone(),
+            diagnostic: Box::new(diagnostic),
+        })?
+        else {
+            return Ok(());
+        };
 
-        let config = serde_json::to_string_pretty(&self.config)?;
+        atomic::write(&path, &text)?;
+        self.document.update(&text);
+        self.loaded =
+            serde_json::to_value(&self.config).expect("the configuration serializes to JSON");
 
-        fs::write(config_path, config)?;
+        Ok(())
+    }
 
+    /// Changes a key of the configuration and saves it, see [`config::ProjectConfiguration::set`].
+    pub fn set(&mut self, key: &str, value: Option<&str>) -> crate::Result<()> {
+        let format = self.config_format();
+        if format.is_version_pin() && key != "version" && key != "mono" {
+            return Err(Error::InvalidSetting {
+                key: key.to_string(),
+                reason: format!(
+                    "{} only pins the engine version, `gdm config export` moves the configuration to a file that can hold it",
+                    format.file_name()
+                ),
+            });
+        }
+
+        // removing a key the project overrides brings back the inherited value
+        let own = self.own_config();
+        let overridden = key
+            .split('.')
+            .try_fold(&own, |value, part| value.get(part))
+            .is_some_and(|value| !value.is_null());
+        match (&self.base, value) {
+            (Some(base), None) if overridden => {
+                let mut own = own;
+                config::assign(&mut own, key, None).map_err(|reason| Error::InvalidSetting {
+                    key: key.to_string(),
+                    reason,
+                })?;
+                let mut resolved = base.value.clone();
+                extends::merge(&mut resolved, own);
+                self.config =
+                    serde_json::from_value(resolved).map_err(|error| Error::InvalidSetting {
+                        key: key.to_string(),
+                        reason: format!("the inherited configuration is incomplete: {error}"),
+                    })?;
+            }
+            _ => self.config.set(key, value)?,
+        }
+        self.save()?;
         Ok(())
     }
 
@@ -52,27 +211,208 @@ impl Project {
         self.dirs.absolute_project_dir.clone()
     }
 
-    pub async fn run(&self, console: bool) -> rootcause::Result<()> {
+    /// The file holding the configuration, e.g. project.json or gdm.toml.
+    pub fn config_path(&self) -> PathBuf {
+        self.path().join(self.config_format().file_name())
+    }
+
+    pub fn config_format(&self) -> format::ConfigFormat {
+        self.document.format()
+    }
+
+    /// What the project inherits through `extends`, `None` when it extends nothing.
+    pub fn base(&self) -> Option<&extends::Base> {
+        self.base.as_ref()
+    }
+
+    /// The configuration without what it inherits unchanged, as the project's own file holds it.
+    pub fn own_config(&self) -> serde_json::Value {
+        let mut value =
+            serde_json::to_value(&self.config).expect("the configuration serializes to JSON");
+        if let Some(base) = &self.base {
+            extends::strip(&mut value, &self.loaded, self.document.value(), &base.value);
+        }
+        value
+    }
+
+    /// Writes the configuration in another format next to the current one, returning its path.
+    ///
+    /// Version pins like `.tool-versions` only receive the engine version, the other formats keep
+    /// extending the same configuration.
+    pub fn export_config(&self, format: format::ConfigFormat) -> crate::Result<PathBuf> {
+        let path = self.path().join(format.file_name());
+        let document = Document::load(format, &self.path())?;
+        let config = match format.is_version_pin() {
+            true => {
+                serde_json::to_value(&self.config).expect("the configuration serializes to JSON")
+            }
+            false => self.own_config(),
+        };
+        let rendered = document
+            .render(config)
+            .map_err(|diagnostic| Error::ConfigParse {
+                path: path.clone(),
+                diagnostic: Box::new(diagnostic),
+            })?;
+        if let Some(text) = rendered {
+            atomic::write(&path, &text)?;
+        }
+        Ok(path)
+    }
+
+    /// Directory the project's engine version is installed to.
+    pub fn engine_dir(&self) -> PathBuf {
+        std::path::absolute(&self.dirs.engines_install_dir)
+            .unwrap_or_else(|_| self.dirs.engines_install_dir.clone())
+            .join(self.config.get_engine_name())
+    }
+
+    pub fn engine_path(&self, console: bool) -> PathBuf {
+        self.engine_dir()
+            .join(self.config.get_engine_file_name(console))
+    }
+
+    pub fn is_engine_installed(&self) -> bool {
+        self.engine_path(false).exists()
+    }
+
+    /// Summary of the project, `upgrade` is the user's policy for projects that set none.
+    pub fn info(&self, upgrade: UpgradePolicy) -> crate::Result<ProjectInfo> {
+        let installed = self.is_engine_installed();
+        let install_path = self.engine_dir();
+
+        Ok(ProjectInfo {
+            name: self.name.clone(),
+            config_path: self.config_path(),
+            constraint: VersionConstraint {
+                // inherited through `extends` when the file itself has none
+                version: self
+                    .document
+                    .version_spec()
+                    .unwrap_or_else(|| self.config.version.to_string()),
+                upgrade: self.config.upgrade.unwrap_or(upgrade),
+            },
+            version: self.config.version.to_string(),
+            mono: self.config.mono,
+            download_source: self.config.download_source.to_string(),
+            disk_usage: if installed {
+                dirs::disk_usage(&install_path)?
+            } else {
+                0
+            },
+            installed,
+            install_path,
+            engine_path: self.engine_path(false),
+        })
+    }
+
+    /// Environment variables pointing tools like godot-rust at the project's engine.
+    pub fn engine_env(&self) -> Vec<(&'static str, OsString)> {
+        let engine_path = self.engine_path(false);
+
+        let mut env = vec![
+            ("GODOT", engine_path.clone().into()),
+            ("GODOT_VERSION", self.config.version.to_string().into()),
+            (
+                "GODOT_MONO",
+                if self.config.mono { "1" } else { "0" }.into(),
+            ),
+        ];
+        if self.config.version.major() != Some(3) {
+            env.push(("GODOT4_BIN", engine_path.into()));
+        }
+        env
+    }
+
+    /// Builds a shell command running in the project directory with the engine environment.
+    pub fn shell(&self, command_line: &str) -> tokio::process::Command {
+        let mut command = process::shell(command_line);
+        command.envs(self.engine_env());
+        command.current_dir(self.path());
+        command
+    }
+
+    /// Launches the engine, returning its exit status for attached runs.
+    pub async fn run(
+        &self,
+        options: &launch::LaunchOptions,
+        progress: &dyn Progress,
+    ) -> crate::Result<Option<ExitStatus>> {
         let project_file = self.path().join("project.godot");
-        if !project_file.exists() {
-            println!("No project.godot file found, creating one...");
+        if options.mode.needs_project() && !project_file.exists() {
+            progress.event(Event::Message(
+                "No project.godot file found, creating one...".to_string(),
+            ));
             fs::write(project_file, "")?;
         }
 
-        let engine_name = self.config.get_engine_name();
-        let engine_file_name = self.config.get_engine_file_name(console);
+        hooks::run(self, hooks::Hook::PreRun, progress).await?;
 
-        let engine_path = &self
-            .dirs
-            .engines_install_dir
-            .join(&engine_name)
-            .join(&engine_file_name);
+        let legacy = self.config.version.major() == Some(3);
 
-        let mut command = std::process::Command::new(engine_path);
-        command.arg("-e");
-        command.current_dir(self.path());
-        command.spawn()?;
+        let engine_path = self.engine_path(options.console);
+        let engine_args = options.engine_args(&self.path(), legacy);
 
-        Ok(())
+        let mut command = tokio::process::Command::new(&engine_path);
+        command.args(&engine_args);
+        command.envs(&options.env);
+        command.stdout(process::stdout(progress));
+        match &options.working_dir {
+            Some(working_dir) => command.current_dir(self.path().join(working_dir)),
+            None => command.current_dir(self.path()),
+        };
+
+        let log = if options.log {
+            let (log_path, mut log_file) = logs::create(self)?;
+            let command_line = engine_args
+                .iter()
+                .map(|arg| arg.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(log_file, "$ {} {command_line}", engine_path.display())?;
+            progress.event(Event::Message(format!(
+                "Logging engine output to {}",
+                log_path.display()
+            )));
+
+            if options.attached {
+                command.stdout(Stdio::piped()).stderr(Stdio::piped());
+            } else {
+                // nobody is left to copy the output once gdm exits, so the engine writes the log itself
+                command
+                    .stdout(log_file.try_clone()?)
+                    .stderr(log_file.try_clone()?);
+            }
+            Some(Arc::new(Mutex::new(log_file)))
+        } else {
+            None
+        };
+
+        let mut child = command.spawn()?;
+
+        if !options.attached {
+            return Ok(None);
+        }
+
+        let mut copies = Vec::new();
+        if let Some(log) = log {
+            if let Some(stdout) = child.stdout.take() {
+                let copy = match progress.redirect_output() {
+                    true => logs::tee(stdout, tokio::io::stderr(), log.clone()),
+                    false => logs::tee(stdout, tokio::io::stdout(), log.clone()),
+                };
+                copies.push(copy);
+            }
+            if let Some(stderr) = child.stderr.take() {
+                copies.push(logs::tee(stderr, tokio::io::stderr(), log));
+            }
+        }
+
+        let status = process::wait(child).await?;
+        for copy in copies {
+            copy.await.map_err(io::Error::other)??;
+        }
+
+        Ok(Some(status))
     }
 }
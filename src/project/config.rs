@@ -1,20 +1,71 @@
-use super::{Project, engine::EngineVersion, versions};
+use super::{
+    Project, diagnostics, document::Document, engine::EngineVersion, format::ConfigFormat,
+    hooks::Hooks, inspect, launch::RunConfiguration, migrations, tasks::Task, versions,
+};
+use crate::Error;
+use crate::settings::{self, Settings};
 use crate::util::dirs::Dirs;
+use crate::util::download::Downloader;
 use crate::util::os::OS;
 use core::fmt;
-use rootcause::bail;
+use rootcause::report;
+use schemars::generate::SchemaSettings;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::str;
 
-#[derive(Deserialize, Serialize, Debug)]
+/// Where the JSON Schema of project.json is published, referenced by `$schema` in new files.
+///
+/// This is the copy in the repository, which a test keeps in line with the configuration.
+pub const JSON_SCHEMA_URL: &str =
+    "https://raw.githubusercontent.com/beak-a-boo/gdm/main/schema/project.schema.json";
+
+/// The configuration of a gdm project, whichever file it is stored in.
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
 pub struct ProjectConfiguration {
+    /// JSON Schema of this file, for completion and validation in editors.
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    pub schema_url: Option<String>,
+    /// Layout version of the file, older files are migrated when they are loaded.
+    #[serde(default)]
+    pub schema: u32,
+    /// Oldest gdm allowed to work on the project, e.g. while a team rolls out an update.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_gdm_version: Option<String>,
+    /// Shared configuration this one builds on, a path relative to this file or a URL, e.g.
+    /// `../gdm.shared.json`. Keys set here override the inherited ones, `null` removes them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// Where the engine is downloaded from.
     pub download_source: EngineDownloadSource,
+    /// Base URLs replacing where a source's releases are downloaded from, taking precedence over
+    /// the user's mirrors.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mirrors: BTreeMap<EngineDownloadSource, String>,
+    /// Which releases `gdm upgrade` moves the project to, the user's setting when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upgrade: Option<UpgradePolicy>,
+    /// Godot Engine version of the project, e.g. `4.3-stable`.
     pub version: EngineVersion,
+    /// Use the .NET (Mono) build of the engine.
     pub mono: bool,
+    /// Named launch profiles for `gdm run <name>`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub runs: BTreeMap<String, RunConfiguration>,
+    /// Named commands for `gdm task <name>`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tasks: BTreeMap<String, Task>,
+    /// Commands run around gdm operations.
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
 }
 
-#[derive(Debug, serde_with::DeserializeFromStr)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, serde_with::DeserializeFromStr)]
 pub enum EngineDownloadSource {
+    #[default]
     GitHub,
     // TODO add tuxfamily
     // https://downloads.tuxfamily.org/godotengine/
@@ -29,12 +80,12 @@ impl fmt::Display for EngineDownloadSource {
 }
 
 impl str::FromStr for EngineDownloadSource {
-    type Err = String;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "github" => Ok(EngineDownloadSource::GitHub),
-            _ => Err(format!("Invalid download source: {}", s)),
+            _ => Err(Error::UnknownDownloadSource(s.to_string())),
         }
     }
 }
@@ -48,60 +99,229 @@ impl serde::ser::Serialize for EngineDownloadSource {
     }
 }
 
+impl JsonSchema for EngineDownloadSource {
+    fn schema_name() -> Cow<'static, str> {
+        "EngineDownloadSource".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "Where the engine is downloaded from.",
+            "type": "string",
+            "enum": ["github"],
+        })
+    }
+}
+
 impl EngineDownloadSource {
-    pub async fn get_latest_version(&self) -> rootcause::Result<EngineVersion> {
+    pub async fn get_latest_version(
+        &self,
+        downloader: &Downloader,
+    ) -> crate::Result<EngineVersion> {
         match self {
-            EngineDownloadSource::GitHub => Ok(versions::get_latest_version_from_github().await?),
+            EngineDownloadSource::GitHub => {
+                versions::get_latest_version_from_github(downloader).await
+            }
+        }
+    }
+
+    /// The version `gdm upgrade` moves `current` to, never an older one.
+    pub async fn get_upgrade_version(
+        &self,
+        current: &EngineVersion,
+        policy: UpgradePolicy,
+        downloader: &Downloader,
+    ) -> crate::Result<EngineVersion> {
+        // how many leading components of the version have to stay the same
+        let fixed = match policy {
+            UpgradePolicy::Latest => return self.get_latest_version(downloader).await,
+            UpgradePolicy::Minor => 1,
+            UpgradePolicy::Patch => 2,
+        };
+
+        let Some(current_parts) = migrations::parse_version(&current.version_string) else {
+            return Ok(EngineVersion::from_string(current.to_string()));
+        };
+        let prefix: Vec<u64> = current_parts
+            .iter()
+            .chain([0; 2].iter())
+            .take(fixed)
+            .copied()
+            .collect();
+
+        Ok(match self.get_newest_version(&prefix, downloader).await? {
+            Some(version)
+                if migrations::parse_version(&version.version_string)
+                    .is_some_and(|parts| parts > current_parts) =>
+            {
+                version
+            }
+            _ => EngineVersion::from_string(current.to_string()),
+        })
+    }
+
+    /// The newest stable release whose version starts with `prefix`, e.g. `[4, 2]` for 4.2.x.
+    pub async fn get_newest_version(
+        &self,
+        prefix: &[u64],
+        downloader: &Downloader,
+    ) -> crate::Result<Option<EngineVersion>> {
+        let versions = match self {
+            EngineDownloadSource::GitHub => versions::get_versions_from_github(downloader).await?,
+        };
+
+        Ok(versions
+            .into_iter()
+            .filter_map(|version| {
+                Some((migrations::parse_version(&version.version_string)?, version))
+            })
+            // 4.0 is parsed as [4], the missing components are zeros
+            .filter(|(parts, _)| {
+                parts
+                    .iter()
+                    .chain([0; 3].iter())
+                    .take(prefix.len())
+                    .eq(prefix.iter())
+            })
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, version)| version))
+    }
+
+    /// Prefix of the URLs release assets are downloaded from, which a mirror replaces.
+    pub fn download_url(&self) -> &'static str {
+        match self {
+            EngineDownloadSource::GitHub => versions::GITHUB_RELEASES_URL,
+        }
+    }
+}
+
+/// Which releases `gdm upgrade` moves a project to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UpgradePolicy {
+    /// The newest stable release.
+    #[default]
+    Latest,
+    /// The newest stable release of the same major version, e.g. 4.x.
+    Minor,
+    /// The newest stable release of the same minor version, e.g. 4.3.x.
+    Patch,
+}
+
+impl fmt::Display for UpgradePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpgradePolicy::Latest => write!(f, "latest"),
+            UpgradePolicy::Minor => write!(f, "minor"),
+            UpgradePolicy::Patch => write!(f, "patch"),
         }
     }
 }
 
 impl ProjectConfiguration {
+    /// Keys of the configuration, for suggestions when one is misspelled.
+    pub const KEYS: &[&str] = &[
+        "$schema",
+        "schema",
+        "min_gdm_version",
+        "extends",
+        "download_source",
+        "mirrors",
+        "upgrade",
+        "version",
+        "mono",
+        "runs",
+        "tasks",
+        "hooks",
+    ];
+
     pub async fn new(
         version: EngineVersion,
         download_source: EngineDownloadSource,
         mono: bool,
-    ) -> rootcause::Result<ProjectConfiguration> {
+    ) -> crate::Result<ProjectConfiguration> {
+        // fail here rather than on every later lookup of the engine's file names
+        OS::current().get_os_string(mono)?;
+
         Ok(ProjectConfiguration {
+            schema_url: None,
+            schema: migrations::SCHEMA,
+            min_gdm_version: None,
+            extends: None,
             download_source,
+            mirrors: BTreeMap::new(),
+            upgrade: None,
             mono,
             version,
+            runs: BTreeMap::new(),
+            tasks: BTreeMap::new(),
+            hooks: Hooks::default(),
         })
     }
 
-    pub async fn init(dirs: &Dirs, mono: bool) -> rootcause::Result<Project> {
+    /// Creates the configuration of a new project, stored in the given format.
+    ///
+    /// The engine build and download source default to the user's settings. For an existing Godot
+    /// project the version and build follow its project.godot, see [`inspect::inspect`].
+    pub async fn init(
+        dirs: &Dirs,
+        settings: &Settings,
+        format: ConfigFormat,
+        downloader: &Downloader,
+    ) -> crate::Result<Project> {
         match std::fs::metadata(&dirs.absolute_project_dir) {
-            Ok(meta) if meta.is_file() => bail!(
-                "Path is a file, not a directory: {}",
-                dirs.absolute_project_dir.display()
-            ),
+            Ok(meta) if meta.is_file() => {
+                return Err(report!(
+                    "Path is a file, not a directory: {}",
+                    dirs.absolute_project_dir.display()
+                )
+                .into());
+            }
             Ok(_) => { /* directory already exists */ }
             Err(_) => std::fs::create_dir_all(&dirs.project_dir)?,
         }
 
-        let config_path = dirs.absolute_project_dir.join("project.json");
-
-        if config_path.exists() {
-            bail!("Project already exists");
+        if !ConfigFormat::detect(&dirs.absolute_project_dir).is_empty() {
+            return Err(Error::ProjectExists {
+                path: dirs.absolute_project_dir.clone(),
+            });
         }
 
-        let directory_name = dirs
-            .absolute_project_dir
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
+        let directory_name = super::project_name(&dirs.absolute_project_dir);
 
-        let source = EngineDownloadSource::GitHub;
-        let version = source.get_latest_version().await?;
+        let source = settings.download_source.clone();
+        // an existing Godot project stays on the engine it was made with instead of the newest one
+        let existing = inspect::inspect(&dirs.absolute_project_dir)?.unwrap_or_default();
+        let inferred = match existing.version.is_empty() {
+            true => None,
+            false => {
+                source
+                    .get_newest_version(&existing.version, downloader)
+                    .await?
+            }
+        };
+        let version = match inferred {
+            Some(version) => version,
+            None => source.get_latest_version(downloader).await?,
+        };
+        let mono = settings.mono || existing.mono;
+
+        let mut config = ProjectConfiguration::new(version, source, mono).await?;
+        if format == ConfigFormat::Json {
+            config.schema_url = Some(JSON_SCHEMA_URL.to_string());
+        }
 
-        let config = ProjectConfiguration::new(version, source, mono).await?; // TODO error handling
+        // project.godot and .tool-versions usually exist already, their other content is kept
+        let document = Document::load(format, &dirs.absolute_project_dir)?;
 
-        let project = Project {
+        let mut project = Project {
             name: directory_name,
             config,
             dirs: dirs.clone(),
+            warnings: Vec::new(),
+            document,
+            base: None,
+            loaded: Value::Null,
         };
 
         project.save()?;
@@ -109,8 +329,87 @@ impl ProjectConfiguration {
         Ok(project)
     }
 
+    /// The value of a key like `mono` or `runs.game.mode`, `None` when it is not set.
+    pub fn get(&self, key: &str) -> crate::Result<Option<Value>> {
+        check_key(key)?;
+        let config = serde_json::to_value(self).expect("the configuration serializes to JSON");
+
+        Ok(key
+            .split('.')
+            .try_fold(&config, |value, part| value.get(part))
+            .cloned())
+    }
+
+    /// Changes a key, removing it when `value` is `None`, and keeps the configuration unchanged
+    /// when the result would be invalid.
+    ///
+    /// Values are read like [`candidate_values`](crate::settings::candidate_values).
+    pub fn set(&mut self, key: &str, value: Option<&str>) -> crate::Result<()> {
+        check_key(key)?;
+        let config = serde_json::to_value(&*self).expect("the configuration serializes to JSON");
+        let invalid = |reason: String| Error::InvalidSetting {
+            key: key.to_string(),
+            reason,
+        };
+
+        let candidates = match value {
+            None => vec![None],
+            Some(value) => settings::candidate_values(value)
+                .into_iter()
+                .map(Some)
+                .collect(),
+        };
+
+        let mut first_error = None;
+        for candidate in candidates {
+            let mut config = config.clone();
+            assign(&mut config, key, candidate).map_err(invalid)?;
+
+            match serde_path_to_error::deserialize::<_, ProjectConfiguration>(config) {
+                Ok(config) => {
+                    // fail now rather than whenever the engine's file names are needed
+                    OS::current().get_os_string(config.mono)?;
+                    *self = config;
+                    return Ok(());
+                }
+                Err(error) => {
+                    first_error.get_or_insert(diagnostics::parse_error(
+                        error.path().to_string(),
+                        error.into_inner(),
+                    ));
+                }
+            }
+        }
+
+        let error = first_error.expect("there is always a candidate");
+        let mut reason = error.message;
+        if error.json_path != "." && error.json_path != key {
+            reason.push_str(&format!(" at `{}`", error.json_path));
+        }
+        if let Some(suggestion) = error.suggestion {
+            reason.push_str(&format!(", did you mean `{suggestion}`?"));
+        }
+        Err(invalid(reason))
+    }
+
+    /// Applies the project's mirrors to a downloader, after the user's settings.
+    pub fn configure(&self, mut downloader: Downloader) -> Downloader {
+        for (source, mirror) in &self.mirrors {
+            downloader = downloader.mirror(source.download_url(), mirror.clone());
+        }
+        downloader
+    }
+
+    /// JSON Schema of the configuration, in draft-07 for the widest editor support.
+    pub fn json_schema() -> Schema {
+        SchemaSettings::draft07()
+            .into_generator()
+            .into_root_schema_for::<ProjectConfiguration>()
+    }
+
     pub fn get_engine_name(&self) -> String {
         let os = OS::current();
+        // checked when the configuration was created or loaded
         let os_string = os.get_os_string(self.mono).expect("Invalid OS");
         let engine_name = self.version.to_string().clone();
 
@@ -129,3 +428,67 @@ impl ProjectConfiguration {
         engine_name
     }
 }
+
+/// Only keys of the configuration can be read and written, with a suggestion for typos.
+fn check_key(key: &str) -> crate::Result<()> {
+    let name = key.split('.').next().unwrap_or_default();
+    if ProjectConfiguration::KEYS.contains(&name) {
+        return Ok(());
+    }
+
+    Err(Error::InvalidSetting {
+        key: key.to_string(),
+        reason: match diagnostics::suggest(name, ProjectConfiguration::KEYS.iter().copied()) {
+            Some(suggestion) => format!("unknown key, did you mean `{suggestion}`?"),
+            None => "unknown key".to_string(),
+        },
+    })
+}
+
+/// Sets a dotted key in a JSON document, creating the objects leading to it.
+pub(super) fn assign(document: &mut Value, key: &str, value: Option<Value>) -> Result<(), String> {
+    let (parents, name) = match key.rsplit_once('.') {
+        Some((parents, name)) => (Some(parents), name),
+        None => (None, key),
+    };
+
+    let mut current = document;
+    for part in parents.into_iter().flat_map(|parents| parents.split('.')) {
+        let Value::Object(object) = current else {
+            return Err(format!("`{part}` is not inside an object"));
+        };
+        current = object
+            .entry(part)
+            .or_insert_with(|| Value::Object(Default::default()));
+    }
+
+    let Value::Object(object) = current else {
+        return Err(format!("`{name}` is not inside an object"));
+    };
+    match value {
+        Some(value) => {
+            object.insert(name.to_string(), value);
+        }
+        None => {
+            object.shift_remove(name);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn published_schema_is_up_to_date() {
+        let published: serde_json::Value =
+            serde_json::from_str(include_str!("../../schema/project.schema.json")).unwrap();
+        let current = serde_json::to_value(ProjectConfiguration::json_schema()).unwrap();
+
+        assert!(
+            published == current,
+            "schema/project.schema.json is outdated, regenerate it with `gdm config schema > schema/project.schema.json`"
+        );
+    }
+}
@@ -0,0 +1,341 @@
+use super::config::ProjectConfiguration;
+use super::format::{self, ConfigFormat};
+use super::{extends, migrations, tasks};
+use crate::util::config_file;
+use core::fmt;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in the configuration.
+#[derive(Serialize, Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Dotted path of the offending value, e.g. `runs.game.mode`, `.` for the document itself.
+    pub json_path: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// The closest valid key or value when the problem looks like a typo.
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, " at line {line}, column {column}")?;
+        }
+        if self.json_path != "." {
+            write!(f, " (`{}`)", self.json_path)?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", did you mean `{suggestion}`?")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the configuration, collecting every problem instead of stopping at a bare serde error.
+///
+/// Older files are migrated to the current schema first, then applied on top of the `base` they
+/// extend. The configuration is `None` when an error prevented reading it, files written for a
+/// newer gdm fail instead.
+pub fn check(
+    text: &str,
+    format: ConfigFormat,
+    base: Option<&Value>,
+) -> crate::Result<(Option<ProjectConfiguration>, Vec<Diagnostic>)> {
+    let mut diagnostics = Vec::new();
+    let mut unknown = Vec::new();
+
+    let mut document = match format.parse(text) {
+        Ok(document) => document,
+        Err(diagnostic) => return Ok((None, vec![diagnostic])),
+    };
+    migrations::check_compatible(&document)?;
+
+    let mut ignored = |path: serde_ignored::Path| unknown.push(path.to_string());
+    let migrated = migrations::migrate(&mut document);
+    if let Some(base) = base {
+        let mut merged = base.clone();
+        extends::merge(&mut merged, document);
+        document = merged;
+    }
+    let result: Result<ProjectConfiguration, _> = if migrated
+        || base.is_some()
+        || format != ConfigFormat::Json
+    {
+        // only errors read straight from JSON text know their location, the others are looked up by key
+        serde_path_to_error::deserialize(serde_ignored::Deserializer::new(document, &mut ignored))
+    } else {
+        let mut json = serde_json::Deserializer::from_str(text);
+        serde_path_to_error::deserialize(serde_ignored::Deserializer::new(&mut json, &mut ignored))
+    };
+
+    for key in unknown {
+        let name = key.rsplit('.').next().unwrap_or(&key).to_string();
+        let (line, column) = locate_key(text, &name, format).unzip();
+        diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            message: format!("Unknown key `{name}` is ignored"),
+            // only the top level keys are known here, nested structures reject unknown keys
+            suggestion: if key.contains('.') {
+                None
+            } else {
+                suggest(&name, ProjectConfiguration::KEYS.iter().copied())
+            },
+            json_path: key,
+            line,
+            column,
+        });
+    }
+
+    let config = match result {
+        Ok(config) => config,
+        Err(error) => {
+            let mut diagnostic = parse_error(error.path().to_string(), error.into_inner());
+            if diagnostic.line.is_none() {
+                let key = diagnostic.json_path.rsplit('.').next().unwrap_or_default();
+                (diagnostic.line, diagnostic.column) = locate_key(text, key, format).unzip();
+            }
+            diagnostics.push(diagnostic);
+            return Ok((None, diagnostics));
+        }
+    };
+
+    if let Some(version) = &config.min_gdm_version
+        && migrations::parse_version(version).is_none()
+    {
+        let (line, column) = locate_key(text, "min_gdm_version", format).unzip();
+        diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            message: format!("`{version}` is not a version like 1.2.0 and is ignored"),
+            json_path: "min_gdm_version".to_string(),
+            line,
+            column,
+            suggestion: None,
+        });
+    }
+
+    Ok((Some(config), diagnostics))
+}
+
+/// Checks that every task can be planned, problems surface as errors of the tasks they start at.
+pub fn check_tasks(
+    config: &ProjectConfiguration,
+    text: &str,
+    format: ConfigFormat,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut failed = Vec::new();
+
+    for (name, task) in &config.tasks {
+        let Err(error) = tasks::plan(&config.tasks, name) else {
+            continue;
+        };
+        failed.push(name);
+        // a task that depends on a broken one fails for the same reason
+        if task
+            .definition()
+            .depends
+            .iter()
+            .any(|dependency| failed.contains(&dependency))
+        {
+            continue;
+        }
+
+        let (line, column) = locate_key(text, name, format).unzip();
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            message: error.to_string(),
+            json_path: format!("tasks.{name}"),
+            line,
+            column,
+            suggestion: None,
+        });
+    }
+
+    diagnostics
+}
+
+pub(crate) fn parse_error(json_path: String, error: serde_json::Error) -> Diagnostic {
+    let message = error.to_string();
+    // the location has fields of its own
+    let message = match message.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_string(),
+        None => message,
+    };
+
+    Diagnostic {
+        severity: Severity::Error,
+        suggestion: suggest_from_message(&message),
+        message,
+        json_path,
+        // errors without a position in the text report line 0
+        line: Some(error.line()).filter(|line| *line > 0),
+        column: Some(error.column()).filter(|_| error.line() > 0),
+    }
+}
+
+/// The candidate closest to `value`, if it is close enough to be a typo.
+pub fn suggest<'a>(value: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let max_distance = (value.chars().count() / 3).max(2);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (strsim::levenshtein(value, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// Suggests a value for serde's "unknown variant `x`, expected one of `a`, `b`" style messages.
+fn suggest_from_message(message: &str) -> Option<String> {
+    let mut quoted = message.split('`').skip(1).step_by(2);
+    let value = quoted.next()?;
+    suggest(value, quoted)
+}
+
+/// Line and column of where `key` is set, 1-based like serde_json's.
+pub(crate) fn locate_key(text: &str, key: &str, format: ConfigFormat) -> Option<(usize, usize)> {
+    let offset = match format {
+        ConfigFormat::Json => find_json_key(text, key),
+        ConfigFormat::Toml => find_assignment(text, key),
+        ConfigFormat::Godot => {
+            let range = config_file::section_range(text, format::GODOT_SECTION)?;
+            let section = &text[range.clone()];
+            // nested keys are inside the JSON-like Variant literals
+            let offset = find_assignment(section, key).or_else(|| find_json_key(section, key))?;
+            Some(range.start + offset)
+        }
+        ConfigFormat::GodotVersion | ConfigFormat::ToolVersions => None,
+    }?;
+
+    Some(position(text, offset))
+}
+
+/// Line and column of a byte offset, 1-based.
+pub(super) fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |newline| newline + 1) + 1;
+    (line, column)
+}
+
+/// Offset of the first `"key":`.
+fn find_json_key(text: &str, key: &str) -> Option<usize> {
+    let needle = format!("\"{key}\"");
+    text.match_indices(&needle).find_map(|(offset, _)| {
+        text[offset + needle.len()..]
+            .trim_start()
+            .starts_with(':')
+            .then_some(offset)
+    })
+}
+
+/// Offset of the first line setting `key = ...`, or of a TOML table header ending in `key`.
+fn find_assignment(text: &str, key: &str) -> Option<usize> {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset + line.len() - line.trim_start().len();
+        offset += line.len();
+
+        let line = line.trim();
+        if let Some(header) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            let name = header
+                .trim_matches(['[', ']'])
+                .rsplit('.')
+                .next()
+                .unwrap_or_default();
+            if name.trim().trim_matches('"') == key {
+                return Some(start);
+            }
+        } else if let Some((name, _)) = line.split_once('=')
+            && name.trim().trim_matches('"') == key
+        {
+            return Some(start);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"{
+  "schema": 1,
+  "download_source": "github",
+  "version": "4.3-stable",
+  "mono": false,
+  "hoks": {},
+  "runs": {
+    "game": {
+      "mode": "gmae"
+    }
+  }
+}
+"#;
+
+    #[test]
+    fn locates_errors_in_json() {
+        let (config, diagnostics) = check(JSON, ConfigFormat::Json, None).unwrap();
+        assert!(config.is_none());
+
+        let error = diagnostics.last().unwrap();
+        assert_eq!(error.severity, Severity::Error);
+        assert_eq!(error.json_path, "runs.game.mode");
+        assert_eq!((error.line, error.column), (Some(9), Some(20)));
+        assert_eq!(error.suggestion.as_deref(), Some("game"));
+        assert!(error.to_string().starts_with("unknown variant `gmae`"));
+        assert!(
+            error
+                .to_string()
+                .ends_with("at line 9, column 20 (`runs.game.mode`), did you mean `game`?")
+        );
+    }
+
+    #[test]
+    fn suggests_known_keys() {
+        let (_, diagnostics) = check(JSON, ConfigFormat::Json, None).unwrap();
+
+        let warning = &diagnostics[0];
+        assert_eq!(warning.severity, Severity::Warning);
+        assert_eq!(warning.message, "Unknown key `hoks` is ignored");
+        assert_eq!(warning.json_path, "hoks");
+        assert_eq!((warning.line, warning.column), (Some(6), Some(3)));
+        assert_eq!(warning.suggestion.as_deref(), Some("hooks"));
+    }
+
+    #[test]
+    fn locates_errors_in_toml_by_key() {
+        let text =
+            "schema = 1\ndownload_source = \"github\"\nversion = \"4.3-stable\"\nmono = \"yes\"\n";
+
+        let (config, diagnostics) = check(text, ConfigFormat::Toml, None).unwrap();
+        assert!(config.is_none());
+
+        let error = diagnostics.last().unwrap();
+        assert_eq!(error.json_path, "mono");
+        assert_eq!((error.line, error.column), (Some(4), Some(1)));
+    }
+
+    #[test]
+    fn suggests_only_close_candidates() {
+        assert_eq!(
+            suggest("verison", ["version", "mono"]).as_deref(),
+            Some("version")
+        );
+        assert_eq!(suggest("platform", ["version", "mono"]), None);
+    }
+}
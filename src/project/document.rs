@@ -0,0 +1,437 @@
+use super::config::ProjectConfiguration;
+use super::diagnostics::{Diagnostic, Severity};
+use super::format::{self, ConfigFormat, GODOT_SECTION};
+use crate::util::config_file::{self, ConfigFile};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::Path;
+use std::{fs, io};
+
+/// The configuration file as it was read, so saving keeps what gdm does not manage itself.
+///
+/// Keys unknown to this gdm, e.g. ones added by a newer version, survive a save, and so do the
+/// order of the keys and the indentation of project.json. In gdm.toml and project.godot only the
+/// keys that changed are rewritten, leaving comments and everything else in place.
+#[derive(Debug, Clone, Default)]
+pub struct Document {
+    format: ConfigFormat,
+    text: String,
+    value: Value,
+    indent: Option<String>,
+}
+
+impl Document {
+    pub fn parse(format: ConfigFormat, text: &str) -> Document {
+        Document {
+            format,
+            text: text.to_string(),
+            value: format.parse(text).unwrap_or_default(),
+            indent: detect_indent(text),
+        }
+    }
+
+    /// The document in `dir`, an empty one when the file does not exist yet.
+    pub fn load(format: ConfigFormat, dir: &Path) -> io::Result<Document> {
+        match fs::read_to_string(dir.join(format.file_name())) {
+            Ok(text) => Ok(Document::parse(format, &text)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                Ok(Document::parse(format, ""))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn format(&self) -> ConfigFormat {
+        self.format
+    }
+
+    /// The document as it was read, before migrations.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// The engine version the document pins, if it has one.
+    pub fn version_pin(&self) -> Option<String> {
+        self.value.get("version").map(|_| pin(&self.value))
+    }
+
+    /// The engine version as the file writes it, e.g. `4.3` where a `.godot-version` leaves out
+    /// the `-stable` it means.
+    pub fn version_spec(&self) -> Option<String> {
+        match self.format.pinned_version(&self.text) {
+            Some((version, _)) => Some(version.to_string()),
+            None => Some(self.value.get("version")?.as_str()?.to_string()),
+        }
+    }
+
+    /// The file's new content, `None` when saving the configuration would not change anything.
+    ///
+    /// `config` is the serialized [`ProjectConfiguration`], the error tells what the format
+    /// cannot hold.
+    pub fn render(&self, config: Value) -> Result<Option<String>, Diagnostic> {
+        let mut value = self.value.clone();
+        merge(&mut value, config, true);
+        if value == self.value {
+            return Ok(None);
+        }
+
+        let text = match self.format {
+            ConfigFormat::Json => self.render_json(&value).map_err(unwritable(".", "JSON"))?,
+            ConfigFormat::Toml => self.render_toml(&value)?,
+            ConfigFormat::Godot => self.render_godot(&value).map_err(unwritable(".", "JSON"))?,
+            ConfigFormat::GodotVersion => format!("{}\n", pin(&value)),
+            ConfigFormat::ToolVersions => self.render_tool_versions(&value),
+        };
+        Ok(Some(text))
+    }
+
+    /// Remembers what was written, so saving it again is a no-op.
+    pub fn update(&mut self, text: &str) {
+        *self = Document::parse(self.format, text);
+    }
+
+    fn render_json(&self, value: &Value) -> serde_json::Result<String> {
+        let indent = self.indent.as_deref().unwrap_or("  ");
+        let mut text = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+        value.serialize(&mut serde_json::Serializer::with_formatter(
+            &mut text, formatter,
+        ))?;
+        text.push(b'\n');
+
+        Ok(String::from_utf8(text).expect("serde_json writes UTF-8"))
+    }
+
+    fn render_toml(&self, value: &Value) -> Result<String, Diagnostic> {
+        let mut document: toml_edit::DocumentMut =
+            self.text.parse().map_err(unwritable(".", "TOML"))?;
+
+        let removed: Vec<String> = document
+            .iter()
+            .map(|(key, _)| key.to_string())
+            .filter(|key| value.get(key).is_none())
+            .collect();
+        for key in removed {
+            document.remove(&key);
+        }
+
+        for (key, new_value) in changed(&self.value, value) {
+            if has_null(new_value) {
+                return Err(Diagnostic {
+                    severity: Severity::Error,
+                    message: format!(
+                        "TOML has no null, so gdm.toml cannot remove `{key}`, which it inherits"
+                    ),
+                    json_path: key.clone(),
+                    line: None,
+                    column: None,
+                    suggestion: None,
+                });
+            }
+            let mut single =
+                toml_edit::ser::to_document(&Map::from_iter([(key.clone(), new_value.clone())]))
+                    .map_err(unwritable(key, "TOML"))?;
+            let mut item = single.remove(key).expect("the key was just serialized");
+            match document.get_mut(key) {
+                Some(existing) => {
+                    // comments around a value stay when it changes
+                    if let (Some(old), Some(new)) = (existing.as_value(), item.as_value_mut()) {
+                        *new.decor_mut() = old.decor().clone();
+                    }
+                    *existing = item;
+                }
+                None => {
+                    document.insert(key, item);
+                }
+            }
+        }
+        // new keys take the same place as in project.json, e.g. `schema` goes first
+        let order: Vec<&String> = value.as_object().into_iter().flat_map(Map::keys).collect();
+        let position = |key: &toml_edit::Key| order.iter().position(|k| *k == key.get());
+        document.sort_values_by(|a, _, b, _| position(a).cmp(&position(b)));
+
+        Ok(document.to_string())
+    }
+
+    fn render_godot(&self, value: &Value) -> serde_json::Result<String> {
+        let file = ConfigFile::parse(&self.text);
+        let section = file.section(GODOT_SECTION);
+
+        let mut entries = Vec::new();
+        for (key, new_value) in value.as_object().into_iter().flatten() {
+            let raw = match section.and_then(|section| section.get(key)) {
+                Some(raw) if self.value.get(key) == Some(new_value) => raw.to_string(),
+                // plain JSON is a valid Variant literal
+                _ => serde_json::to_string(new_value)?,
+            };
+            entries.push((key.clone(), raw));
+        }
+
+        Ok(config_file::replace_section(
+            &self.text,
+            GODOT_SECTION,
+            &entries,
+        ))
+    }
+
+    /// Replaces the version of the `godot` entry, keeping the other tools.
+    fn render_tool_versions(&self, value: &Value) -> String {
+        let entry = format!("godot {}", pin(value));
+
+        let mut replaced = false;
+        let mut lines: Vec<String> = self
+            .text
+            .lines()
+            .map(|line| {
+                if replaced || format::tool_versions_entry(line).is_none() {
+                    return line.to_string();
+                }
+                replaced = true;
+                match line.find('#') {
+                    Some(comment) => format!("{entry} {}", &line[comment..]),
+                    None => entry.clone(),
+                }
+            })
+            .collect();
+        if !replaced {
+            lines.push(entry);
+        }
+
+        lines.join("\n") + "\n"
+    }
+}
+
+/// Reports why the configuration cannot be written in `format`, at `json_path`.
+fn unwritable<E: std::fmt::Display>(json_path: &str, format: &str) -> impl FnOnce(E) -> Diagnostic {
+    let json_path = json_path.to_string();
+    let format = format.to_string();
+    move |error| Diagnostic {
+        severity: Severity::Error,
+        message: format!("Cannot be written as {format}: {error}"),
+        json_path,
+        line: None,
+        column: None,
+        suggestion: None,
+    }
+}
+
+fn pin(value: &Value) -> String {
+    format::version_pin(
+        value["version"].as_str().unwrap_or_default(),
+        value["mono"].as_bool().unwrap_or_default(),
+    )
+}
+
+/// Top level keys of `new` whose value differs from the one in `old`.
+fn changed<'a>(old: &Value, new: &'a Value) -> impl Iterator<Item = (&'a String, &'a Value)> {
+    new.as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, value)| old.get(key.as_str()) != Some(value))
+}
+
+/// Whether `value` removes an inherited key somewhere, see [`extends::merge`](super::extends::merge).
+fn has_null(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Object(object) => object.values().any(has_null),
+        _ => false,
+    }
+}
+
+/// Updates `old` to `new` in place, keeping the position of existing keys.
+///
+/// With `keep_unknown`, keys that are not part of the configuration are left untouched.
+fn merge(old: &mut Value, new: Value, keep_unknown: bool) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(mut new)) => {
+            old.retain(|key, _| {
+                new.contains_key(key)
+                    || (keep_unknown && !ProjectConfiguration::KEYS.contains(&key.as_str()))
+            });
+            for (key, value) in old.iter_mut() {
+                if let Some(new_value) = new.shift_remove(key) {
+                    merge(value, new_value, false);
+                }
+            }
+            for (key, value) in new {
+                // both schema keys lead the file like they do in newly created ones
+                if key == "$schema" {
+                    old.shift_insert(0, key, value);
+                } else if key == "schema" {
+                    let index = old.contains_key("$schema") as usize;
+                    old.shift_insert(index, key, value);
+                } else {
+                    old.insert(key, value);
+                }
+            }
+        }
+        (old, new) => {
+            if *old != new {
+                *old = new;
+            }
+        }
+    }
+}
+
+/// Leading whitespace of the first indented line, the file's unit of indentation.
+fn detect_indent(text: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let indent: String = line
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        (!indent.is_empty() && indent.len() < line.len()).then_some(indent)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(version: &str) -> Value {
+        json!({
+            "schema": 1,
+            "download_source": "github",
+            "version": version,
+            "mono": false,
+        })
+    }
+
+    #[test]
+    fn keeps_unknown_keys_and_indentation_of_json() {
+        let text = r#"{
+    "schema": 1,
+    "future": {"kept": true},
+    "download_source": "github",
+    "version": "4.2-stable",
+    "mono": false,
+    "runs": {
+        "game": {"mode": "game"}
+    }
+}
+"#;
+        let document = Document::parse(ConfigFormat::Json, text);
+
+        assert_eq!(
+            document.render(config("4.3-stable")).unwrap().unwrap(),
+            r#"{
+    "schema": 1,
+    "future": {
+        "kept": true
+    },
+    "download_source": "github",
+    "version": "4.3-stable",
+    "mono": false
+}
+"#
+        );
+    }
+
+    #[test]
+    fn leaves_unchanged_files_alone() {
+        let text =
+            r#"{"schema": 1, "download_source": "github", "version": "4.3-stable", "mono": false}"#;
+        let document = Document::parse(ConfigFormat::Json, text);
+
+        assert_eq!(document.render(config("4.3-stable")).unwrap(), None);
+    }
+
+    #[test]
+    fn keeps_comments_of_toml() {
+        let text = r#"# engine shared by the team
+schema = 1
+download_source = "github"
+version = "4.2-stable" # pinned for CI
+mono = false
+future = "kept"
+
+[runs.game]
+mode = "game"
+"#;
+        let document = Document::parse(ConfigFormat::Toml, text);
+
+        assert_eq!(
+            document.render(config("4.3-stable")).unwrap().unwrap(),
+            r#"# engine shared by the team
+schema = 1
+download_source = "github"
+version = "4.3-stable" # pinned for CI
+mono = false
+future = "kept"
+"#
+        );
+    }
+
+    #[test]
+    fn replaces_only_the_gdm_section_of_project_godot() {
+        let text = r#"; Engine configuration file.
+config_version=5
+
+[application]
+
+; the name shown in the window title
+config/name="Game"
+
+[gdm]
+
+schema=1
+download_source="github"
+version="4.2-stable"
+mono=false
+runs={"game": {"mode": "game"}}
+future="kept"
+
+[rendering]
+
+renderer/rendering_method="mobile"
+"#;
+        let document = Document::parse(ConfigFormat::Godot, text);
+
+        assert_eq!(
+            document.render(config("4.3-stable")).unwrap().unwrap(),
+            r#"; Engine configuration file.
+config_version=5
+
+[application]
+
+; the name shown in the window title
+config/name="Game"
+
+[gdm]
+
+schema=1
+download_source="github"
+version="4.3-stable"
+mono=false
+future="kept"
+
+[rendering]
+
+renderer/rendering_method="mobile"
+"#
+        );
+    }
+
+    #[test]
+    fn reads_the_version_as_written() {
+        let spec = |format, text| Document::parse(format, text).version_spec();
+        assert_eq!(
+            spec(ConfigFormat::GodotVersion, "# engine\n4.3_mono\n"),
+            Some("4.3".to_string())
+        );
+        assert_eq!(
+            spec(ConfigFormat::ToolVersions, "godot 4.4-beta1\n"),
+            Some("4.4-beta1".to_string())
+        );
+        assert_eq!(
+            spec(ConfigFormat::Toml, "version = \"4.3-stable\"\n"),
+            Some("4.3-stable".to_string())
+        );
+        assert_eq!(
+            spec(ConfigFormat::Json, r#"{"extends": "base.json"}"#),
+            None
+        );
+    }
+}
@@ -1,7 +1,9 @@
 use core::fmt;
 use std::str::FromStr;
 
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
 use serde::Serializer;
+use std::borrow::Cow;
 
 #[derive(Debug, serde_with::DeserializeFromStr, Eq)]
 pub struct EngineVersion {
@@ -10,7 +12,7 @@ pub struct EngineVersion {
 }
 
 impl FromStr for EngineVersion {
-    type Err = String;
+    type Err = std::convert::Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(EngineVersion::from_string(s.to_string()))
@@ -47,6 +49,20 @@ impl serde::ser::Serialize for EngineVersion {
     }
 }
 
+impl JsonSchema for EngineVersion {
+    fn schema_name() -> Cow<'static, str> {
+        "EngineVersion".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "A Godot Engine release as tagged on GitHub, e.g. `4.3-stable`.",
+            "type": "string",
+            "examples": ["4.3-stable", "4.4-beta1", "3.6-stable"],
+        })
+    }
+}
+
 impl EngineVersion {
     pub fn from_string(s: String) -> EngineVersion {
         let (version, build) = match s.split_once('-') {
@@ -59,4 +75,8 @@ impl EngineVersion {
             build_string,
         }
     }
+
+    pub fn major(&self) -> Option<u32> {
+        self.version_string.split('.').next()?.parse().ok()
+    }
 }
@@ -0,0 +1,204 @@
+use super::{Project, versions};
+use crate::Error;
+use crate::util::archive;
+use crate::util::config_file::ConfigFile;
+use crate::util::download::Downloader;
+use crate::util::os::OS;
+use crate::util::progress::{Event, Progress};
+use directories::BaseDirs;
+use rootcause::{bail, report};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::{fs, process};
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ExportPreset {
+    pub name: String,
+    pub platform: String,
+    pub export_path: String,
+    pub runnable: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportMode {
+    Release,
+    Debug,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ExportResult {
+    pub preset: String,
+    pub output: PathBuf,
+    pub success: bool,
+}
+
+impl ExportPreset {
+    pub fn is_valid(&self) -> bool {
+        !self.export_path.is_empty()
+    }
+}
+
+pub fn load_presets(project: &Project) -> rootcause::Result<Vec<ExportPreset>> {
+    let presets_file = project.path().join("export_presets.cfg");
+    if !presets_file.exists() {
+        bail!(
+            "No export_presets.cfg found in {}, add export presets in the Godot editor first",
+            project.path().display()
+        );
+    }
+
+    let config = ConfigFile::load(&presets_file)?;
+
+    // options live in `[preset.N.options]`, the preset itself in `[preset.N]`
+    let presets = config
+        .sections()
+        .filter(|section| {
+            section
+                .name
+                .strip_prefix("preset.")
+                .is_some_and(|index| index.parse::<u32>().is_ok())
+        })
+        .map(|section| ExportPreset {
+            name: section.get_string("name").unwrap_or_default(),
+            platform: section.get_string("platform").unwrap_or_default(),
+            export_path: section.get_string("export_path").unwrap_or_default(),
+            runnable: section.get_bool("runnable").unwrap_or(false),
+        })
+        .collect();
+
+    Ok(presets)
+}
+
+/// Directory Godot looks for the export templates of the project's engine version in.
+pub fn templates_dir(project: &Project) -> rootcause::Result<PathBuf> {
+    let base_dirs = BaseDirs::new().ok_or(report!("Could not read global directories"))?;
+    let godot_dir = if OS::current() == OS::Linux {
+        "godot"
+    } else {
+        "Godot"
+    };
+
+    let version = &project.config.version;
+    let templates = match version.major() {
+        Some(3) => "templates",
+        _ => "export_templates",
+    };
+
+    let mut version_dir = match &version.build_string {
+        Some(build) => format!("{}.{}", version.version_string, build),
+        None => format!("{}.stable", version.version_string),
+    };
+    if project.config.mono {
+        version_dir.push_str(".mono");
+    }
+
+    Ok(base_dirs
+        .data_dir()
+        .join(godot_dir)
+        .join(templates)
+        .join(version_dir))
+}
+
+pub async fn ensure_templates_installed(
+    project: &Project,
+    downloader: &Downloader,
+    progress: &dyn Progress,
+) -> crate::Result<bool> {
+    let templates_dir = templates_dir(project)?;
+    if templates_dir.join("version.txt").exists() {
+        return Ok(false);
+    }
+
+    let file_name = if project.config.mono {
+        format!(
+            "Godot_v{}_mono_export_templates.tpz",
+            project.config.version
+        )
+    } else {
+        format!("Godot_v{}_export_templates.tpz", project.config.version)
+    };
+    progress.event(Event::Resolve {
+        version: project.config.version.to_string(),
+        engine: file_name.clone(),
+    });
+
+    let archive_path = project.dirs.cache_dir.join("templates").join(&file_name);
+    versions::download_from_github(
+        &archive_path,
+        file_name.clone(),
+        project,
+        downloader,
+        progress,
+    )
+    .await?;
+
+    progress.event(Event::Extract {
+        target: templates_dir.clone(),
+    });
+    archive::extract(&archive_path, &templates_dir, Some(true)).map_err(|source| {
+        Error::Extract {
+            archive: archive_path.clone(),
+            source,
+        }
+    })?;
+    fs::remove_file(&archive_path)?;
+
+    progress.event(Event::Done {
+        name: file_name,
+        path: templates_dir,
+    });
+
+    Ok(true)
+}
+
+pub fn export(
+    project: &Project,
+    preset: &ExportPreset,
+    mode: ExportMode,
+    out_dir: Option<&Path>,
+) -> rootcause::Result<ExportResult> {
+    if !preset.is_valid() {
+        bail!("Preset \"{}\" has no export path", preset.name);
+    }
+
+    let output = match out_dir {
+        Some(out_dir) => {
+            let file_name = Path::new(&preset.export_path)
+                .file_name()
+                .ok_or(report!("Invalid export path: {}", preset.export_path))?;
+            std::path::absolute(out_dir)?.join(file_name)
+        }
+        None => project.path().join(&preset.export_path),
+    };
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let legacy = project.config.version.major() == Some(3);
+    let export_flag = match (mode, legacy) {
+        (ExportMode::Release, false) => "--export-release",
+        (ExportMode::Debug, false) => "--export-debug",
+        (ExportMode::Release, true) => "--export",
+        (ExportMode::Debug, true) => "--export-debug",
+    };
+
+    // the console wrapper is the only way to see the engine's output on windows
+    let mut command = process::Command::new(project.engine_path(OS::current().is_windows()));
+    command
+        .arg(if legacy { "--no-window" } else { "--headless" })
+        .arg("--path")
+        .arg(project.path())
+        .arg(export_flag)
+        .arg(&preset.name)
+        .arg(&output)
+        .current_dir(project.path());
+
+    let status = command.status()?;
+
+    Ok(ExportResult {
+        preset: preset.name.clone(),
+        success: status.success() && output.exists(),
+        output,
+    })
+}
@@ -0,0 +1,399 @@
+use super::format::ConfigFormat;
+use super::migrations;
+use crate::Error;
+use crate::util::atomic;
+use crate::util::dirs::{self, Dirs};
+use crate::util::download::Downloader;
+use path_clean::PathClean;
+use reqwest::Url;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+/// Longest chain of `extends`, a longer one is almost certainly a mistake.
+const MAX_DEPTH: usize = 16;
+
+/// What a project inherits through `extends`, every file of the chain merged into one.
+#[derive(Debug, Clone)]
+pub struct Base {
+    pub value: Value,
+    /// The files and URLs the configuration extends, the closest first.
+    pub sources: Vec<String>,
+}
+
+/// A file a configuration extends.
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    Path(PathBuf),
+    Url(Url),
+}
+
+impl Source {
+    /// `reference` as written in a file at `from`, paths are relative to the file's directory.
+    fn resolve(reference: &str, from: &Source) -> Result<Source, String> {
+        if let Ok(url) = Url::parse(reference)
+            && matches!(url.scheme(), "http" | "https")
+        {
+            return Ok(Source::Url(url));
+        }
+        match from {
+            Source::Path(path) => Ok(Source::Path(
+                path.parent()
+                    .unwrap_or(Path::new("."))
+                    .join(reference)
+                    .clean(),
+            )),
+            Source::Url(url) => url
+                .join(reference)
+                .map(Source::Url)
+                .map_err(|error| error.to_string()),
+        }
+    }
+
+    /// Shared files may be written in TOML, anything else is read as JSON.
+    fn format(&self) -> ConfigFormat {
+        let is_toml = match self {
+            Source::Path(path) => path
+                .extension()
+                .is_some_and(|extension| extension == "toml"),
+            Source::Url(url) => url.path().ends_with(".toml"),
+        };
+        match is_toml {
+            true => ConfigFormat::Toml,
+            false => ConfigFormat::Json,
+        }
+    }
+}
+
+/// Where a configuration downloaded from `url` is cached.
+pub fn cache_path(dirs: &Dirs, url: &str) -> PathBuf {
+    let extension = match url.ends_with(".toml") {
+        true => "toml",
+        false => "json",
+    };
+    dirs.cache_dir.join("extends").join(format!(
+        "{:016x}.{extension}",
+        dirs::stable_hash(url.as_bytes())
+    ))
+}
+
+/// Resolves the `extends` chain of the configuration `text` in the project directory `dir`,
+/// `None` when it extends nothing or cannot be parsed, which checking it reports.
+///
+/// Files downloaded from a URL are read from the cache, which [`fetch`] fills.
+pub fn resolve(
+    text: &str,
+    format: ConfigFormat,
+    dir: &Path,
+    dirs: &Dirs,
+) -> crate::Result<Option<Base>> {
+    walk(text, format, dir, |source| match source {
+        Source::Path(path) => Ok(fs::read_to_string(path)?),
+        Source::Url(url) => match fs::read_to_string(cache_path(dirs, url.as_str())) {
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Err(Error::ExtendsNotCached {
+                url: url.to_string(),
+            }),
+            result => Ok(result?),
+        },
+    })
+}
+
+/// Downloads every URL in the `extends` chain of the configuration `text` to the cache,
+/// returning them.
+pub async fn fetch(
+    text: &str,
+    format: ConfigFormat,
+    dir: &Path,
+    dirs: &Dirs,
+    downloader: &Downloader,
+) -> crate::Result<Vec<String>> {
+    let mut fetched: Vec<String> = Vec::new();
+    loop {
+        // a downloaded file may extend further URLs, so the chain is walked again after each one
+        let mut missing = None;
+        let result = walk(text, format, dir, |source| match source {
+            Source::Path(path) => Ok(fs::read_to_string(path)?),
+            Source::Url(url) if !fetched.contains(&url.to_string()) => {
+                missing = Some(url.to_string());
+                Err(Error::ExtendsNotCached {
+                    url: url.to_string(),
+                })
+            }
+            Source::Url(url) => Ok(fs::read_to_string(cache_path(dirs, url.as_str()))?),
+        });
+
+        let Some(url) = missing else {
+            result?;
+            return Ok(fetched);
+        };
+        let Some(text) = downloader.get_text(url.clone()).await? else {
+            return Err(Error::Extends {
+                extends: url,
+                reason: "the server has no such file".to_string(),
+            });
+        };
+        let path = cache_path(dirs, &url);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        atomic::write(&path, &text)?;
+        fetched.push(url);
+    }
+}
+
+fn walk(
+    text: &str,
+    format: ConfigFormat,
+    dir: &Path,
+    mut read: impl FnMut(&Source) -> crate::Result<String>,
+) -> crate::Result<Option<Base>> {
+    let Ok(document) = format.parse(text) else {
+        return Ok(None);
+    };
+    // relative paths in the project's file start at its directory
+    let mut from = Source::Path(dir.join(ConfigFormat::default().file_name()));
+    let mut reference = extends(&document);
+    let mut visited = Vec::new();
+    let mut layers = Vec::new();
+
+    while let Some(current) = reference {
+        let error = |reason: String| Error::Extends {
+            extends: current.clone(),
+            reason,
+        };
+        let source = Source::resolve(&current, &from).map_err(error)?;
+        if visited.contains(&source) {
+            return Err(error("the configuration extends itself".to_string()));
+        }
+        if visited.len() == MAX_DEPTH {
+            return Err(error(format!(
+                "more than {MAX_DEPTH} files extend each other"
+            )));
+        }
+
+        let text = match read(&source) {
+            Err(Error::Io(io)) => return Err(error(io.to_string())),
+            result => result?,
+        };
+        let mut layer = source
+            .format()
+            .parse(&text)
+            .map_err(|diagnostic| error(format!("{diagnostic}")))?;
+        migrations::check_compatible(&layer)?;
+        migrations::migrate(&mut layer);
+        let Value::Object(mut object) = layer else {
+            return Err(error("the file does not hold an object".to_string()));
+        };
+
+        reference = object
+            .shift_remove("extends")
+            .and_then(|value| value.as_str().map(str::to_string));
+        layers.push(Value::Object(object));
+        visited.push(source.clone());
+        from = source;
+    }
+
+    if layers.is_empty() {
+        return Ok(None);
+    }
+    let mut value = Value::Object(Map::new());
+    // every file overrides the ones it extends
+    for layer in layers.into_iter().rev() {
+        merge(&mut value, layer);
+    }
+    let sources = visited
+        .into_iter()
+        .map(|source| match source {
+            Source::Path(path) => path.display().to_string(),
+            Source::Url(url) => url.to_string(),
+        })
+        .collect();
+
+    Ok(Some(Base { value, sources }))
+}
+
+/// The file a configuration extends, if any.
+fn extends(document: &Value) -> Option<String> {
+    document
+        .get("extends")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// Applies `layer` on top of `base`: objects are merged key by key, other values replace the
+/// inherited ones and `null` removes them.
+pub fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                match (base.get_mut(&key), value) {
+                    (_, Value::Null) => {
+                        base.shift_remove(&key);
+                    }
+                    (Some(inherited), value) => merge(inherited, value),
+                    (None, value) => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Reduces `value`, the configuration about to be saved, to what the project's own file holds.
+///
+/// Keys unchanged since the configuration was `loaded` keep what `own`, the file, has for them,
+/// and are left out when they were inherited, like changed keys that match the base again.
+/// Inherited keys that were removed become `null`.
+pub fn strip(value: &mut Value, loaded: &Value, own: &Value, base: &Value) {
+    let Value::Object(object) = value else {
+        return;
+    };
+
+    let removed: Vec<String> = base
+        .as_object()
+        .into_iter()
+        .flat_map(Map::keys)
+        .filter(|key| !object.contains_key(*key))
+        .filter(|key| loaded.get(key).is_some() || own.get(key).is_some())
+        .cloned()
+        .collect();
+
+    let keys: Vec<String> = object.keys().cloned().collect();
+    for key in keys {
+        let (loaded, own) = (loaded.get(&key), own.get(&key));
+        let current = &object[&key];
+        if Some(current) == loaded {
+            match own {
+                Some(own) => object[&key] = own.clone(),
+                None => {
+                    object.shift_remove(&key);
+                }
+            }
+        } else if base.get(&key) == Some(current) {
+            // a changed value that matches the base is inherited again
+            object.shift_remove(&key);
+        } else if let (Some(loaded), Value::Object(_)) = (loaded, current) {
+            let base = base.get(&key).unwrap_or(&Value::Null);
+            let own = own.unwrap_or(&Value::Null);
+            strip(&mut object[&key], loaded, own, base);
+        }
+    }
+
+    for key in removed {
+        object.insert(key, Value::Null);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merges_objects_key_by_key() {
+        let mut base = json!({
+            "version": "4.2-stable",
+            "mono": true,
+            "hooks": {"pre_run": "cargo build", "post_install": "./setup.sh"},
+        });
+        merge(
+            &mut base,
+            json!({"version": "4.3-stable", "mono": null, "hooks": {"pre_run": "make"}}),
+        );
+
+        assert_eq!(
+            base,
+            json!({
+                "version": "4.3-stable",
+                "hooks": {"pre_run": "make", "post_install": "./setup.sh"},
+            })
+        );
+    }
+
+    #[test]
+    fn strips_inherited_values() {
+        let base = json!({
+            "download_source": "github",
+            "version": "4.2-stable",
+            "mono": false,
+            "hooks": {"pre_run": "cargo build"},
+            "tasks": {"test": "cargo test"},
+        });
+        let own = json!({"extends": "../shared.json", "version": "4.3-stable"});
+        let mut loaded = base.clone();
+        merge(&mut loaded, own.clone());
+        loaded["schema"] = json!(1);
+
+        // nothing changed, so the file keeps exactly what it had
+        let mut value = loaded.clone();
+        strip(&mut value, &loaded, &own, &base);
+        assert_eq!(value, own);
+
+        let mut value = loaded.clone();
+        value["mono"] = json!(true);
+        value["version"] = json!("4.2-stable");
+        value["hooks"]["post_install"] = json!("./setup.sh");
+        value.as_object_mut().unwrap().shift_remove("tasks");
+        strip(&mut value, &loaded, &own, &base);
+        assert_eq!(
+            value,
+            json!({
+                "extends": "../shared.json",
+                "mono": true,
+                "hooks": {"post_install": "./setup.sh"},
+                "tasks": null,
+            })
+        );
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let dir = std::env::temp_dir().join(format!("gdm-extends-{}", std::process::id()));
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        fs::write(
+            dir.join("shared/a.json"),
+            r#"{"extends": "b.json", "mono": true}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("shared/b.json"),
+            r#"{"extends": "./a.json", "version": "4.3-stable"}"#,
+        )
+        .unwrap();
+        let dirs = Dirs {
+            project_dir: dir.clone(),
+            absolute_project_dir: dir.clone(),
+            cache_dir: dir.join("cache"),
+            data_dir: dir.join("data"),
+            download_dir: dir.join("downloads"),
+            engines_install_dir: dir.join("engines"),
+        };
+
+        let result = resolve(
+            r#"{"extends": "shared/a.json"}"#,
+            ConfigFormat::Json,
+            &dir,
+            &dirs,
+        );
+        let missing = resolve(
+            r#"{"extends": "shared/missing.json"}"#,
+            ConfigFormat::Json,
+            &dir,
+            &dirs,
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        match result {
+            Err(Error::Extends { extends, reason }) => {
+                // the reference closing the loop
+                assert_eq!(extends, "./a.json");
+                assert_eq!(reason, "the configuration extends itself");
+            }
+            result => panic!("expected a cycle, got {result:?}"),
+        }
+        // a missing file is reported like any other problem with the chain
+        assert!(matches!(missing, Err(Error::Extends { .. })));
+    }
+}
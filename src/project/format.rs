@@ -0,0 +1,253 @@
+use super::diagnostics::{self, Diagnostic, Severity};
+use super::migrations;
+use crate::util::config_file::ConfigFile;
+use core::fmt;
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::path::Path;
+use std::str;
+
+/// Section of project.godot holding the configuration.
+pub const GODOT_SECTION: &str = "gdm";
+
+/// Where a project keeps its configuration, every format holds the same
+/// [`ProjectConfiguration`](super::config::ProjectConfiguration).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// `project.json`
+    #[default]
+    Json,
+    /// `gdm.toml`
+    Toml,
+    /// The `[gdm]` section of `project.godot`, one Variant literal per top level key.
+    Godot,
+    /// `.godot-version`, holding nothing but the engine version.
+    GodotVersion,
+    /// The `godot` entry of the `.tool-versions` file used by asdf and mise.
+    ToolVersions,
+}
+
+impl ConfigFormat {
+    /// Every format, in the order they take precedence when a project has several.
+    pub const ALL: [ConfigFormat; 5] = [
+        ConfigFormat::Json,
+        ConfigFormat::Toml,
+        ConfigFormat::Godot,
+        ConfigFormat::GodotVersion,
+        ConfigFormat::ToolVersions,
+    ];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "project.json",
+            ConfigFormat::Toml => "gdm.toml",
+            ConfigFormat::Godot => "project.godot",
+            ConfigFormat::GodotVersion => ".godot-version",
+            ConfigFormat::ToolVersions => ".tool-versions",
+        }
+    }
+
+    /// Whether the format only pins the engine version, other settings are lost when writing it.
+    pub fn is_version_pin(self) -> bool {
+        matches!(
+            self,
+            ConfigFormat::GodotVersion | ConfigFormat::ToolVersions
+        )
+    }
+
+    /// The formats a configuration exists in for the project in `dir`, by precedence.
+    pub fn detect(dir: &Path) -> Vec<ConfigFormat> {
+        ConfigFormat::ALL
+            .into_iter()
+            .filter(|format| {
+                let path = dir.join(format.file_name());
+                match format {
+                    // every Godot project has a project.godot, only the section makes it a configuration
+                    ConfigFormat::Godot => ConfigFile::load(&path)
+                        .is_ok_and(|file| file.section(GODOT_SECTION).is_some()),
+                    // the file usually pins other tools as well
+                    ConfigFormat::ToolVersions => fs::read_to_string(&path)
+                        .is_ok_and(|text| tool_versions_entry(&text).is_some()),
+                    _ => path.is_file(),
+                }
+            })
+            .collect()
+    }
+
+    /// Reads the configuration into a JSON document, which migrations and validation work on.
+    pub fn parse(self, text: &str) -> Result<Value, Diagnostic> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(text)
+                .map_err(|error| diagnostics::parse_error(".".to_string(), error)),
+            ConfigFormat::Toml => toml_edit::de::from_str(text).map_err(|error| {
+                let (line, column) = error
+                    .span()
+                    .map(|span| diagnostics::position(text, span.start))
+                    .unzip();
+                Diagnostic {
+                    severity: Severity::Error,
+                    message: error.message().trim_end().to_string(),
+                    json_path: ".".to_string(),
+                    line,
+                    column,
+                    suggestion: None,
+                }
+            }),
+            ConfigFormat::Godot => {
+                let file = ConfigFile::parse(text);
+                let mut document = Map::new();
+                for (key, value) in file
+                    .section(GODOT_SECTION)
+                    .into_iter()
+                    .flat_map(|s| s.entries())
+                {
+                    // the Variant literals gdm writes are plain JSON
+                    let value = serde_json::from_str(value).map_err(|error| {
+                        let (line, column) = diagnostics::locate_key(text, key, self).unzip();
+                        Diagnostic {
+                            severity: Severity::Error,
+                            message: format!("Not a plain value: {error}"),
+                            json_path: key.to_string(),
+                            line,
+                            column,
+                            suggestion: None,
+                        }
+                    })?;
+                    document.insert(key.to_string(), value);
+                }
+                Ok(Value::Object(document))
+            }
+            ConfigFormat::GodotVersion | ConfigFormat::ToolVersions => {
+                let Some((version, mono)) = self.pinned_version(text) else {
+                    return Err(Diagnostic {
+                        severity: Severity::Error,
+                        message: "No Godot version is pinned".to_string(),
+                        json_path: ".".to_string(),
+                        line: None,
+                        column: None,
+                        suggestion: None,
+                    });
+                };
+
+                // a bare `4.3` means the stable release
+                let version = match version.contains('-') {
+                    true => version.to_string(),
+                    false => format!("{version}-stable"),
+                };
+
+                Ok(json!({
+                    "schema": migrations::SCHEMA,
+                    "download_source": "github",
+                    "version": version,
+                    "mono": mono,
+                }))
+            }
+        }
+    }
+
+    /// The engine version a version pin holds as written, e.g. `4.3` and mono for `4.3_mono`.
+    pub fn pinned_version(self, text: &str) -> Option<(&str, bool)> {
+        let pin = match self {
+            ConfigFormat::GodotVersion => text
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty() && !line.starts_with('#')),
+            ConfigFormat::ToolVersions => tool_versions_entry(text),
+            _ => None,
+        }?;
+        match pin.strip_suffix("_mono").or(pin.strip_suffix("-mono")) {
+            Some(version) => Some((version, true)),
+            None => Some((pin, false)),
+        }
+    }
+}
+
+/// How a version pin writes the engine version, e.g. `4.3-stable_mono`.
+pub fn version_pin(version: &str, mono: bool) -> String {
+    match mono {
+        true => format!("{version}_mono"),
+        false => version.to_string(),
+    }
+}
+
+/// The first version listed for `godot` in a `.tool-versions` file.
+pub fn tool_versions_entry(text: &str) -> Option<&str> {
+    text.lines().find_map(|line| {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        (fields.next()? == "godot").then(|| fields.next())?
+    })
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigFormat::Json => write!(f, "json"),
+            ConfigFormat::Toml => write!(f, "toml"),
+            ConfigFormat::Godot => write!(f, "godot"),
+            ConfigFormat::GodotVersion => write!(f, "godot-version"),
+            ConfigFormat::ToolVersions => write!(f, "tool-versions"),
+        }
+    }
+}
+
+impl str::FromStr for ConfigFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(ConfigFormat::Json),
+            "toml" => Ok(ConfigFormat::Toml),
+            "godot" => Ok(ConfigFormat::Godot),
+            "godot-version" => Ok(ConfigFormat::GodotVersion),
+            "tool-versions" => Ok(ConfigFormat::ToolVersions),
+            _ => Err(format!(
+                "Unknown configuration format `{s}`, expected `json`, `toml`, `godot`, `godot-version` or `tool-versions`"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pin(format: ConfigFormat, text: &str) -> (String, bool) {
+        let document = format.parse(text).unwrap();
+        (
+            document["version"].as_str().unwrap().to_string(),
+            document["mono"].as_bool().unwrap(),
+        )
+    }
+
+    #[test]
+    fn reads_godot_version() {
+        let pin = |text| pin(ConfigFormat::GodotVersion, text);
+
+        assert_eq!(pin("4.3\n"), ("4.3-stable".to_string(), false));
+        assert_eq!(pin("# engine\n4.4-rc1\n"), ("4.4-rc1".to_string(), false));
+        assert_eq!(pin("4.3-stable_mono"), ("4.3-stable".to_string(), true));
+        assert_eq!(pin("4.3-mono"), ("4.3-stable".to_string(), true));
+    }
+
+    #[test]
+    fn reads_the_first_godot_entry_of_tool_versions() {
+        let text = "nodejs 22.1.0\ngodot 4.2.2 # pinned\ngodot 4.3\n";
+
+        assert_eq!(
+            pin(ConfigFormat::ToolVersions, text),
+            ("4.2.2-stable".to_string(), false)
+        );
+        assert_eq!(
+            pin(ConfigFormat::ToolVersions, "godot 4.3_mono 4.2"),
+            ("4.3-stable".to_string(), true)
+        );
+        assert_eq!(tool_versions_entry("godot-cli 1.0\n"), None);
+    }
+
+    #[test]
+    fn reports_missing_pins() {
+        assert!(ConfigFormat::GodotVersion.parse("# nothing\n").is_err());
+        assert!(ConfigFormat::ToolVersions.parse("nodejs 22.1.0\n").is_err());
+    }
+}
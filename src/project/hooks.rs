@@ -0,0 +1,105 @@
+use super::Project;
+use crate::util::process;
+use crate::util::progress::{Event, Progress};
+use core::fmt;
+use rootcause::bail;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::{OneOrMany, formats::PreferOne, serde_as};
+
+/// Commands run around gdm operations, each hook takes a single command or a list of them.
+#[serde_as]
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    /// Runs before the engine is launched.
+    #[serde_as(as = "OneOrMany<_, PreferOne>")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(with = "Commands")]
+    pub pre_run: Vec<String>,
+    /// Runs after the engine was downloaded.
+    #[serde_as(as = "OneOrMany<_, PreferOne>")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(with = "Commands")]
+    pub post_install: Vec<String>,
+    /// Runs before `gdm upgrade` changes the engine version.
+    #[serde_as(as = "OneOrMany<_, PreferOne>")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(with = "Commands")]
+    pub pre_upgrade: Vec<String>,
+    /// Runs after `gdm upgrade` changed the engine version, with the new engine installed.
+    #[serde_as(as = "OneOrMany<_, PreferOne>")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(with = "Commands")]
+    pub post_upgrade: Vec<String>,
+}
+
+/// Schema of a hook, the fields are deserialized through `OneOrMany`.
+#[derive(JsonSchema)]
+#[serde(untagged)]
+#[allow(dead_code)]
+enum Commands {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hook {
+    PreRun,
+    PostInstall,
+    PreUpgrade,
+    PostUpgrade,
+}
+
+impl fmt::Display for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Hook::PreRun => write!(f, "pre_run"),
+            Hook::PostInstall => write!(f, "post_install"),
+            Hook::PreUpgrade => write!(f, "pre_upgrade"),
+            Hook::PostUpgrade => write!(f, "post_upgrade"),
+        }
+    }
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.pre_run.is_empty()
+            && self.post_install.is_empty()
+            && self.pre_upgrade.is_empty()
+            && self.post_upgrade.is_empty()
+    }
+
+    pub fn commands(&self, hook: Hook) -> &[String] {
+        match hook {
+            Hook::PreRun => &self.pre_run,
+            Hook::PostInstall => &self.post_install,
+            Hook::PreUpgrade => &self.pre_upgrade,
+            Hook::PostUpgrade => &self.post_upgrade,
+        }
+    }
+}
+
+/// Runs the hook's commands in order, failing on the first command that exits unsuccessfully.
+pub async fn run(project: &Project, hook: Hook, progress: &dyn Progress) -> rootcause::Result<()> {
+    for command_line in project.config.hooks.commands(hook) {
+        progress.event(Event::Command {
+            label: hook.to_string(),
+            command: command_line.clone(),
+        });
+
+        let mut command = project.shell(command_line);
+        command.env("GDM_HOOK", hook.to_string());
+        command.stdout(process::stdout(progress));
+
+        let status = process::wait(command.spawn()?).await?;
+        if !status.success() {
+            bail!(
+                "The {hook} hook \"{command_line}\" {}, aborting",
+                process::describe(&status)
+            );
+        }
+    }
+
+    Ok(())
+}
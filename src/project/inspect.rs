@@ -0,0 +1,194 @@
+use crate::util::config_file::{self, ConfigFile};
+use serde::Serialize;
+use std::path::Path;
+use std::{fs, io};
+
+/// What an existing Godot project reveals about the engine it was made with.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct GodotProject {
+    /// Leading components of the engine version, e.g. `[4, 2]`, empty when nothing hints at it.
+    pub version: Vec<u64>,
+    /// Where the version was read from, e.g. `config/features`.
+    pub version_hint: Option<String>,
+    /// Whether the project uses C#, which needs the Mono build.
+    pub mono: bool,
+    /// What revealed C#, e.g. `Game.csproj`.
+    pub mono_hint: Option<String>,
+}
+
+/// Reads the project.godot in `dir`, `None` when there is none.
+pub fn inspect(dir: &Path) -> io::Result<Option<GodotProject>> {
+    let file = match ConfigFile::load(&dir.join("project.godot")) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error),
+    };
+    let mut project = GodotProject::default();
+
+    // Godot 4 writes config_version 5, Godot 3 writes 4
+    let major = match file.global().get_int("config_version") {
+        Some(5) => Some(4),
+        Some(4) => Some(3),
+        _ => None,
+    };
+    if let Some(major) = major {
+        project.version = vec![major];
+        project.version_hint = Some("config_version".to_string());
+    }
+
+    // the features list the minor version the project was last saved with, e.g. "4.2"
+    let features = file
+        .section("application")
+        .and_then(|section| section.get("config/features"))
+        .map(config_file::string_literals)
+        .unwrap_or_default();
+    if let Some(version) = features.iter().find_map(|feature| {
+        let version = feature_version(feature)?;
+        (version.len() >= 2 && major.is_none_or(|major| version[0] == major)).then_some(version)
+    }) {
+        project.version = version;
+        project.version_hint = Some("config/features".to_string());
+    }
+
+    // C# projects come with a .csproj and configure their assembly in [dotnet], [mono] in Godot 3
+    let csproj = fs::read_dir(dir)?.find_map(|entry| {
+        let path = entry.ok()?.path();
+        let is_csproj = path
+            .extension()
+            .is_some_and(|extension| extension == "csproj");
+        is_csproj.then(|| path.file_name()?.to_str().map(str::to_string))?
+    });
+    let section = ["dotnet", "mono"]
+        .into_iter()
+        .find(|name| file.section(name).is_some());
+    project.mono_hint =
+        csproj.or(section.map(|name| format!("the [{name}] section of project.godot")));
+    project.mono = project.mono_hint.is_some();
+
+    Ok(Some(project))
+}
+
+/// The version of a feature like `4.2`, `None` for features like `Forward Plus`.
+fn feature_version(feature: &str) -> Option<Vec<u64>> {
+    feature
+        .split('.')
+        .map(|component| component.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inspects a project made of `files`, each a file name and its content.
+    fn inspect_files(name: &str, files: &[(&str, &str)]) -> Option<GodotProject> {
+        let dir = std::env::temp_dir().join(format!("gdm-inspect-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, content) in files {
+            fs::write(dir.join(file), content).unwrap();
+        }
+        let project = inspect(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        project
+    }
+
+    #[test]
+    fn reads_the_major_version_from_config_version() {
+        let godot4 = inspect_files("godot4", &[("project.godot", "config_version=5\n")]).unwrap();
+        assert_eq!(godot4.version, [4]);
+        assert_eq!(godot4.version_hint.as_deref(), Some("config_version"));
+
+        let godot3 = inspect_files("godot3", &[("project.godot", "config_version=4\n")]).unwrap();
+        assert_eq!(godot3.version, [3]);
+
+        let unknown = inspect_files("unknown", &[("project.godot", "config_version=2\n")]);
+        assert_eq!(unknown.unwrap().version, Vec::<u64>::new());
+    }
+
+    #[test]
+    fn reads_the_minor_version_from_the_features() {
+        let project = inspect_files(
+            "features",
+            &[(
+                "project.godot",
+                "config_version=5\n\n[application]\n\nconfig/name=\"Game\"\n\
+                 config/features=PackedStringArray(\"4.2\", \"Forward Plus\")\n",
+            )],
+        )
+        .unwrap();
+        assert_eq!(project.version, [4, 2]);
+        assert_eq!(project.version_hint.as_deref(), Some("config/features"));
+        assert!(!project.mono);
+    }
+
+    #[test]
+    fn reads_a_godot_3_project() {
+        let project = inspect_files(
+            "godot3-mono",
+            &[(
+                "project.godot",
+                "config_version=4\n\n[application]\n\nconfig/name=\"Old\"\n\n\
+                 [mono]\n\nproject/assembly_name=\"Old\"\n",
+            )],
+        )
+        .unwrap();
+        assert_eq!(project.version, [3]);
+        assert_eq!(project.version_hint.as_deref(), Some("config_version"));
+        assert!(project.mono);
+        assert_eq!(
+            project.mono_hint.as_deref(),
+            Some("the [mono] section of project.godot")
+        );
+    }
+
+    #[test]
+    fn keeps_the_major_version_without_features() {
+        let project = inspect_files(
+            "no-features",
+            &[(
+                "project.godot",
+                "config_version=5\n\n[application]\n\nconfig/name=\"Game\"\n",
+            )],
+        )
+        .unwrap();
+        assert_eq!(project.version, [4]);
+        assert_eq!(project.version_hint.as_deref(), Some("config_version"));
+    }
+
+    #[test]
+    fn detects_csharp() {
+        let csproj = inspect_files(
+            "csproj",
+            &[
+                (
+                    "project.godot",
+                    "config_version=5\n\n[dotnet]\n\nproject/assembly_name=\"Game\"\n",
+                ),
+                ("Game.csproj", "<Project Sdk=\"Godot.NET.Sdk/4.2.0\" />\n"),
+            ],
+        )
+        .unwrap();
+        assert!(csproj.mono);
+        assert_eq!(csproj.mono_hint.as_deref(), Some("Game.csproj"));
+
+        let dotnet = inspect_files(
+            "dotnet",
+            &[(
+                "project.godot",
+                "config_version=5\n\n[dotnet]\n\nproject/assembly_name=\"Game\"\n",
+            )],
+        )
+        .unwrap();
+        assert!(dotnet.mono);
+        assert_eq!(
+            dotnet.mono_hint.as_deref(),
+            Some("the [dotnet] section of project.godot")
+        );
+    }
+
+    #[test]
+    fn finds_nothing_without_a_project() {
+        assert_eq!(inspect_files("empty", &[("README.md", "# Game\n")]), None);
+    }
+}
@@ -0,0 +1,199 @@
+use rootcause::bail;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum LaunchMode {
+    /// Open the project in the editor.
+    #[default]
+    Editor,
+    /// Run the game, starting with the given scene or the project's main scene.
+    Game { scene: Option<String> },
+    /// Run the game without a window or audio.
+    Headless,
+    /// Open the project manager instead of the project.
+    ProjectManager,
+    /// Run a script without a window.
+    Script { script: PathBuf },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    pub mode: LaunchMode,
+    pub console: bool,
+    /// Wait for the engine to exit instead of returning right after launching it.
+    pub attached: bool,
+    /// Write the engine's output to a new log file in the project's data directory.
+    pub log: bool,
+    /// Additional arguments passed to the engine after the mode's own arguments.
+    pub args: Vec<String>,
+    /// Environment variables set for the engine in addition to gdm's own environment.
+    pub env: BTreeMap<String, String>,
+    /// Working directory of the engine, relative to the project directory.
+    pub working_dir: Option<PathBuf>,
+}
+
+/// A named launch profile from the `runs` section of project.json.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RunConfiguration {
+    #[serde(default)]
+    pub mode: RunMode,
+    /// Scene to start in `game` mode, relative to the project, e.g. `res://levels/test.tscn`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scene: Option<String>,
+    /// Script to run in `script` mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<PathBuf>,
+    /// Additional arguments passed to the engine.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Environment variables set for the engine.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Working directory of the engine, relative to the project directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// Use the console binary on Windows.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub console: bool,
+    /// Wait for the engine to exit, defaults to waiting for every mode but `editor` and `project-manager`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attach: Option<bool>,
+}
+
+/// How a run configuration launches the engine.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunMode {
+    #[default]
+    Editor,
+    Game,
+    Headless,
+    ProjectManager,
+    Script,
+}
+
+impl RunConfiguration {
+    pub fn launch_mode(&self) -> rootcause::Result<LaunchMode> {
+        Ok(match self.mode {
+            RunMode::Editor => LaunchMode::Editor,
+            RunMode::Game => LaunchMode::Game {
+                scene: self.scene.clone(),
+            },
+            RunMode::Headless => LaunchMode::Headless,
+            RunMode::ProjectManager => LaunchMode::ProjectManager,
+            RunMode::Script => match &self.script {
+                Some(script) => LaunchMode::Script {
+                    script: script.clone(),
+                },
+                None => bail!("Run configurations with mode \"script\" need a \"script\""),
+            },
+        })
+    }
+
+    pub fn launch_options(&self) -> rootcause::Result<LaunchOptions> {
+        let mode = self.launch_mode()?;
+        Ok(LaunchOptions {
+            attached: self.attach.unwrap_or(mode.attaches_by_default()),
+            mode,
+            console: self.console,
+            log: false,
+            args: self.args.clone(),
+            env: self.env.clone(),
+            working_dir: self.cwd.clone(),
+        })
+    }
+}
+
+impl LaunchOptions {
+    /// Every argument the engine is launched with for the project in `project_dir`.
+    ///
+    /// Godot looks for project.godot in its working directory, so runs started elsewhere pass
+    /// the project's path.
+    pub fn engine_args(&self, project_dir: &Path, legacy: bool) -> Vec<OsString> {
+        let mut args = Vec::new();
+        if self.working_dir.is_some() && self.mode.needs_project() {
+            args.push("--path".into());
+            args.push(project_dir.into());
+        }
+        args.extend(self.mode.engine_args(legacy));
+        args.extend(self.args.iter().map(OsString::from));
+        args
+    }
+}
+
+impl LaunchMode {
+    /// Engine arguments for this mode, `legacy` selects the Godot 3 spelling where it differs.
+    pub fn engine_args(&self, legacy: bool) -> Vec<OsString> {
+        let headless = if legacy { "--no-window" } else { "--headless" };
+
+        match self {
+            LaunchMode::Editor => vec!["-e".into()],
+            LaunchMode::Game { scene } => scene.iter().map(OsString::from).collect(),
+            LaunchMode::Headless => vec![headless.into()],
+            LaunchMode::ProjectManager => vec!["-p".into()],
+            LaunchMode::Script { script } => {
+                vec![headless.into(), "-s".into(), script.into()]
+            }
+        }
+    }
+
+    /// Headless and script runs are usually automation, which needs to know how the engine exited.
+    pub fn attaches_by_default(&self) -> bool {
+        matches!(self, LaunchMode::Headless | LaunchMode::Script { .. })
+    }
+
+    pub fn needs_project(&self) -> bool {
+        self != &LaunchMode::ProjectManager
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_the_project_path_from_another_working_directory() {
+        let options = LaunchOptions {
+            mode: LaunchMode::Headless,
+            args: vec!["--verbose".to_string()],
+            working_dir: Some(PathBuf::from("tests")),
+            ..LaunchOptions::default()
+        };
+
+        assert_eq!(
+            options.engine_args(Path::new("/game"), false),
+            ["--path", "/game", "--headless", "--verbose"]
+        );
+    }
+
+    #[test]
+    fn runs_in_the_project_directory_without_a_path() {
+        let options = LaunchOptions {
+            mode: LaunchMode::Game {
+                scene: Some("res://main.tscn".to_string()),
+            },
+            ..LaunchOptions::default()
+        };
+
+        assert_eq!(
+            options.engine_args(Path::new("/game"), true),
+            ["res://main.tscn"]
+        );
+    }
+
+    #[test]
+    fn opens_the_project_manager_without_a_path() {
+        let options = LaunchOptions {
+            mode: LaunchMode::ProjectManager,
+            working_dir: Some(PathBuf::from("tools")),
+            ..LaunchOptions::default()
+        };
+
+        assert_eq!(options.engine_args(Path::new("/game"), false), ["-p"]);
+    }
+}
@@ -0,0 +1,145 @@
+use super::Project;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::task::JoinHandle;
+
+/// Number of runs kept per project, older logs are deleted when a new run starts.
+pub const MAX_LOGS: usize = 10;
+
+pub fn logs_dir(project: &Project) -> PathBuf {
+    project.dirs.project_data_dir().join("logs")
+}
+
+/// All logs of the project, most recent run first.
+pub fn list(project: &Project) -> io::Result<Vec<PathBuf>> {
+    let dir = logs_dir(project);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut logs = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+        .collect::<Vec<_>>();
+
+    // file names are zero padded timestamps, so they sort chronologically
+    logs.sort();
+    logs.reverse();
+
+    Ok(logs)
+}
+
+/// Creates the log file for a new run and rotates out the oldest ones.
+pub fn create(project: &Project) -> io::Result<(PathBuf, File)> {
+    let dir = logs_dir(project);
+    fs::create_dir_all(&dir)?;
+
+    for old in list(project)?.iter().skip(MAX_LOGS - 1) {
+        fs::remove_file(old)?;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = dir.join(format!("{timestamp:020}.log"));
+    let file = File::create(&path)?;
+
+    Ok((path, file))
+}
+
+/// Copies a child output stream line by line to both `output` and the shared log file.
+pub fn tee<R, W>(input: R, mut output: W, log: Arc<Mutex<File>>) -> JoinHandle<io::Result<()>>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(input);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line).await? == 0 {
+                return Ok(());
+            }
+            output.write_all(&line).await?;
+            output.flush().await?;
+            log.lock()
+                .expect("log file lock poisoned")
+                .write_all(&line)?;
+        }
+    })
+}
+
+/// Prints the log and keeps printing whatever gets appended to it, like `tail -f`.
+///
+/// The log does not tell when its run ends, so this never returns on its own but loops until gdm
+/// is interrupted, e.g. with Ctrl+C.
+pub async fn follow(path: &Path) -> io::Result<()> {
+    let mut file = File::open(path)?;
+    let mut stdout = io::stdout();
+    let mut buffer = Vec::new();
+
+    loop {
+        buffer.clear();
+        file.read_to_end(&mut buffer)?;
+        if buffer.is_empty() {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+            continue;
+        }
+        stdout.write_all(&buffer)?;
+        stdout.flush()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::dirs::Dirs;
+
+    #[test]
+    fn keeps_the_most_recent_logs() {
+        let dir = std::env::temp_dir().join(format!("gdm-logs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("project.json"),
+            r#"{"schema": 1, "download_source": "github", "version": "4.3-stable", "mono": false}"#,
+        )
+        .unwrap();
+        let dirs = Dirs {
+            project_dir: dir.clone(),
+            absolute_project_dir: dir.clone(),
+            cache_dir: dir.join("cache"),
+            data_dir: dir.join("data"),
+            download_dir: dir.join("downloads"),
+            engines_install_dir: dir.join("engines"),
+        };
+        let project = Project::load(&dirs).unwrap();
+
+        let logs = logs_dir(&project);
+        fs::create_dir_all(&logs).unwrap();
+        for run in 1..=MAX_LOGS + 2 {
+            fs::write(logs.join(format!("{run:020}.log")), "").unwrap();
+        }
+        fs::write(logs.join("notes.txt"), "").unwrap();
+        let (created, _) = create(&project).unwrap();
+        let listed = list(&project).unwrap();
+        let notes = logs.join("notes.txt").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(listed.len(), MAX_LOGS);
+        assert_eq!(listed[0], created);
+        // the newest of the older runs follow, the three oldest are gone
+        let kept = (4..=MAX_LOGS + 2)
+            .rev()
+            .map(|run| logs.join(format!("{run:020}.log")))
+            .collect::<Vec<_>>();
+        assert_eq!(listed[1..], kept);
+        assert!(notes);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::Error;
+use serde_json::{Map, Value};
+
+/// Schema version of project.json written by this gdm.
+pub const SCHEMA: u32 = 1;
+
+/// Upgrades a document by one schema version, `MIGRATIONS[n]` turns schema `n` into `n + 1`.
+type Migration = fn(&mut Map<String, Value>);
+
+const MIGRATIONS: [Migration; SCHEMA as usize] = [
+    // schema 0 is every file written before the `schema` key existed, its layout is unchanged
+    |_| {},
+];
+
+/// Fails when the document was written for a newer gdm than this one.
+pub fn check_compatible(document: &Value) -> crate::Result<()> {
+    if let Some(required) = document.get("min_gdm_version").and_then(Value::as_str)
+        && is_older(crate::VERSION, required)
+    {
+        return Err(Error::GdmTooOld {
+            required: required.to_string(),
+            current: crate::VERSION.to_string(),
+        });
+    }
+
+    if let Some(schema) = document.get("schema").and_then(Value::as_u64)
+        && schema > SCHEMA as u64
+    {
+        return Err(Error::SchemaTooNew {
+            schema,
+            supported: SCHEMA,
+        });
+    }
+
+    Ok(())
+}
+
+/// Whether the gdm version `current` is older than `required`.
+///
+/// A `current` version that is not a number, like the `DEV` of source builds, counts as newer
+/// than every release, as those are built from the latest sources. An invalid `required` version
+/// is reported by checking the configuration and ignored here.
+fn is_older(current: &str, required: &str) -> bool {
+    match (parse_version(current), parse_version(required)) {
+        (Some(current), Some(required)) => current < required,
+        _ => false,
+    }
+}
+
+/// Brings an older document up to [`SCHEMA`], returns whether anything changed.
+pub fn migrate(document: &mut Value) -> bool {
+    let Some(object) = document.as_object_mut() else {
+        return false;
+    };
+    let schema = match object.get("schema") {
+        None => 0,
+        Some(schema) => match schema.as_u64() {
+            Some(schema) => schema as usize,
+            // left for deserialization to report
+            None => return false,
+        },
+    };
+
+    if schema >= MIGRATIONS.len() {
+        return false;
+    }
+    for migration in &MIGRATIONS[schema..] {
+        migration(object);
+    }
+    // right after `$schema`, like in newly created files
+    let index = object.contains_key("$schema") as usize;
+    object.shift_insert(index, "schema".to_string(), SCHEMA.into());
+    true
+}
+
+/// Numeric components of a version like `1.4.0` or `v1.4.0-rc1`, ignoring pre-release suffixes.
+pub fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim().trim_start_matches('v');
+    let version = version.split(['-', '+']).next()?;
+    let mut components = version
+        .split('.')
+        .map(|component| component.parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+
+    // 1.4 and 1.4.0 are the same version
+    while components.last() == Some(&0) {
+        components.pop();
+    }
+    Some(components)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!(parse_version("v1.4.0-rc1"), Some(vec![1, 4]));
+        assert_eq!(parse_version("1.10"), parse_version("1.10.0"));
+        assert_eq!(parse_version("DEV"), None);
+    }
+
+    #[test]
+    fn compares_gdm_versions() {
+        assert!(is_older("24.1231.1200", "25.0101"));
+        assert!(!is_older("25.0101.0900", "25.0101"));
+        assert!(!is_older("DEV", "25.0101"));
+        assert!(!is_older("25.0101.0900", "latest"));
+    }
+
+    #[test]
+    fn rejects_files_for_newer_gdm() {
+        assert!(matches!(
+            check_compatible(&json!({ "schema": SCHEMA + 1 })),
+            Err(Error::SchemaTooNew { .. })
+        ));
+        assert!(check_compatible(&json!({ "schema": SCHEMA, "min_gdm_version": "0.1" })).is_ok());
+    }
+
+    #[test]
+    fn migrates_files_without_schema() {
+        let mut document = json!({ "$schema": "schema.json", "version": "4.3-stable" });
+
+        assert!(migrate(&mut document));
+        assert_eq!(
+            document.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["$schema", "schema", "version"]
+        );
+        assert!(!migrate(&mut document));
+    }
+}
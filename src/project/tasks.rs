@@ -0,0 +1,189 @@
+use super::Project;
+use crate::util::process;
+use crate::util::progress::{Event, Progress};
+use rootcause::bail;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// A task from the `tasks` section of project.json, either a plain shell command or a full definition.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone)]
+#[serde(untagged)]
+pub enum Task {
+    Command(String),
+    Definition(TaskDefinition),
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct TaskDefinition {
+    /// Shell command to run, tasks without one only run their dependencies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Tasks that run before this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends: Vec<String>,
+    /// Working directory of the command, relative to the project directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// Environment variables set for the command.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+impl Task {
+    pub fn definition(&self) -> TaskDefinition {
+        match self {
+            Task::Command(command) => TaskDefinition {
+                command: Some(command.clone()),
+                ..Default::default()
+            },
+            Task::Definition(definition) => definition.clone(),
+        }
+    }
+}
+
+/// Orders the task and all of its dependencies so that every task comes after the tasks it depends on.
+pub fn plan(tasks: &BTreeMap<String, Task>, name: &str) -> rootcause::Result<Vec<String>> {
+    fn visit(
+        tasks: &BTreeMap<String, Task>,
+        name: &str,
+        stack: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> rootcause::Result<()> {
+        if order.iter().any(|done| done == name) {
+            return Ok(());
+        }
+        if stack.iter().any(|pending| pending == name) {
+            stack.push(name.to_string());
+            bail!("Task dependencies form a cycle: {}", stack.join(" -> "));
+        }
+
+        let Some(task) = tasks.get(name) else {
+            match stack.last() {
+                Some(parent) => bail!("Task \"{parent}\" depends on unknown task \"{name}\""),
+                None => bail!(
+                    "Unknown task \"{name}\", available tasks: {}",
+                    tasks
+                        .keys()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            }
+        };
+
+        stack.push(name.to_string());
+        for dependency in task.definition().depends {
+            visit(tasks, &dependency, stack, order)?;
+        }
+        stack.pop();
+
+        order.push(name.to_string());
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    visit(tasks, name, &mut Vec::new(), &mut order)?;
+    Ok(order)
+}
+
+/// Runs the task after its dependencies, with the project's engine exported to the environment.
+///
+/// Returns the names of all tasks that ran, in order.
+pub async fn run(
+    project: &Project,
+    name: &str,
+    progress: &dyn Progress,
+) -> rootcause::Result<Vec<String>> {
+    let tasks = &project.config.tasks;
+    let order = plan(tasks, name)?;
+
+    for name in &order {
+        let definition = tasks[name].definition();
+        let Some(command_line) = definition.command else {
+            continue;
+        };
+
+        progress.event(Event::Command {
+            label: name.clone(),
+            command: command_line.clone(),
+        });
+
+        let mut command = project.shell(&command_line);
+        command.envs(&definition.env);
+        command.stdout(process::stdout(progress));
+        if let Some(cwd) = &definition.cwd {
+            command.current_dir(project.path().join(cwd));
+        }
+
+        let status = process::wait(command.spawn()?).await?;
+        if !status.success() {
+            bail!("Task \"{name}\" {}", process::describe(&status));
+        }
+    }
+
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks(definitions: &[(&str, &[&str])]) -> BTreeMap<String, Task> {
+        definitions
+            .iter()
+            .map(|(name, depends)| {
+                let task = Task::Definition(TaskDefinition {
+                    command: Some(format!("echo {name}")),
+                    depends: depends.iter().map(|name| name.to_string()).collect(),
+                    ..Default::default()
+                });
+                (name.to_string(), task)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn orders_dependencies_first() {
+        let tasks = tasks(&[
+            ("build", &["generate", "fetch"]),
+            ("export", &["build", "generate"]),
+            ("fetch", &[]),
+            ("generate", &["fetch"]),
+        ]);
+
+        assert_eq!(
+            plan(&tasks, "export").unwrap(),
+            ["fetch", "generate", "build", "export"]
+        );
+    }
+
+    #[test]
+    fn reports_cycles() {
+        let tasks = tasks(&[("a", &["b"]), ("b", &["c"]), ("c", &["a"])]);
+
+        let error = plan(&tasks, "a").unwrap_err().to_string();
+        assert!(
+            error.contains("Task dependencies form a cycle: a -> b -> c -> a"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn reports_unknown_tasks() {
+        let tasks = tasks(&[("build", &["generate"]), ("test", &[])]);
+
+        let error = plan(&tasks, "build").unwrap_err().to_string();
+        assert!(
+            error.contains("Task \"build\" depends on unknown task \"generate\""),
+            "{error}"
+        );
+        let error = plan(&tasks, "deploy").unwrap_err().to_string();
+        assert!(
+            error.contains("Unknown task \"deploy\", available tasks: build, test"),
+            "{error}"
+        );
+    }
+}
@@ -1,35 +1,128 @@
+use crate::Error;
+use crate::util::download::Downloader;
 use crate::util::os::OS;
-use crate::util::{archive, download};
+use crate::util::progress::{Event, Progress};
+use crate::util::{archive, atomic, download};
 use serde::{Deserialize, Serialize};
 use std::{fs, path::PathBuf};
 
-use super::{Project, engine::EngineVersion};
+use super::{
+    Project,
+    engine::EngineVersion,
+    hooks::{self, Hook},
+};
+
+/// Where Godot's release assets are downloaded from, mirrors replace this prefix.
+pub const GITHUB_RELEASES_URL: &str = "https://github.com/godotengine/godot/releases/download";
 
 #[derive(Serialize, Deserialize, Debug)]
 struct GithubReleaseResponse {
     pub tag_name: String,
+    #[serde(default)]
+    pub prerelease: bool,
 }
 
-pub async fn get_latest_version_from_github() -> Result<EngineVersion, download::DownloadError> {
+pub async fn get_latest_version_from_github(
+    downloader: &Downloader,
+) -> crate::Result<EngineVersion> {
     let url = "https://api.github.com/repos/godotengine/godot/releases/latest";
-    let response = download::get_json::<GithubReleaseResponse>(url.to_string()).await?;
+    let response = downloader
+        .get_json::<GithubReleaseResponse>(url.to_string())
+        .await?;
 
     Ok(EngineVersion::from_string(response.tag_name))
 }
 
+/// The stable releases on GitHub, newest first, all pages of them so older versions are found too.
+pub async fn get_versions_from_github(
+    downloader: &Downloader,
+) -> crate::Result<Vec<EngineVersion>> {
+    let url = "https://api.github.com/repos/godotengine/godot/releases?per_page=100";
+    let response = downloader
+        .get_json_pages::<GithubReleaseResponse>(url.to_string())
+        .await?;
+
+    Ok(response
+        .into_iter()
+        .filter(|release| !release.prerelease)
+        .map(|release| EngineVersion::from_string(release.tag_name))
+        .collect())
+}
+
 pub async fn download_from_github(
     path: &PathBuf,
     filename: String,
     project: &Project,
-) -> Result<u64, download::DownloadError> {
+    downloader: &Downloader,
+    progress: &dyn Progress,
+) -> crate::Result<u64> {
+    let downloader = &project.config.configure(downloader.clone());
     let url = format!(
-        "https://github.com/godotengine/godot/releases/download/{}/{}",
+        "{GITHUB_RELEASES_URL}/{}/{}",
         &project.config.version, filename
     );
-    download::download_file(url, path, &project.dirs).await
+    let size = match downloader
+        .download_file(url, path, &project.dirs, progress)
+        .await
+    {
+        Err(error) if error.is_not_found() => {
+            return Err(Error::VersionNotFound {
+                version: project.config.version.to_string(),
+                file: filename,
+            });
+        }
+        result => result?,
+    };
+
+    verify_checksum(path, &filename, project, downloader, progress).await?;
+
+    Ok(size)
 }
 
-pub async fn ensure_version_installed(project: &Project) -> rootcause::Result<()> {
+/// Checks a release asset against the release's SHA512-SUMS.txt, older releases without one are not verified.
+pub async fn verify_checksum(
+    path: &PathBuf,
+    filename: &str,
+    project: &Project,
+    downloader: &Downloader,
+    progress: &dyn Progress,
+) -> crate::Result<()> {
+    let url = format!(
+        "{GITHUB_RELEASES_URL}/{}/SHA512-SUMS.txt",
+        &project.config.version
+    );
+    let Some(sums) = downloader.get_text(url).await? else {
+        return Ok(());
+    };
+
+    let Some(expected) = sums.lines().find_map(|line| {
+        let (hash, name) = line.split_once(char::is_whitespace)?;
+        (name.trim().trim_start_matches('*') == filename).then(|| hash.to_lowercase())
+    }) else {
+        return Ok(());
+    };
+
+    progress.event(Event::Verify { file: path.clone() });
+
+    let actual = download::sha512(path)?;
+    if actual != expected {
+        fs::remove_file(path)?;
+        return Err(Error::ChecksumMismatch {
+            file: filename.to_string(),
+            expected,
+            actual,
+        });
+    }
+
+    Ok(())
+}
+
+/// Downloads the project's engine version unless it is installed already, returns whether it was downloaded.
+pub async fn ensure_version_installed(
+    project: &Project,
+    downloader: &Downloader,
+    progress: &dyn Progress,
+) -> crate::Result<bool> {
     let config = &project.config;
     let engine_name = config.get_engine_name();
     let engine_file_name = config.get_engine_file_name(false);
@@ -39,8 +132,23 @@ pub async fn ensure_version_installed(project: &Project) -> rootcause::Result<()
     let engine_dir = dirs.engines_install_dir.join(&engine_name);
     let engine_file = engine_dir.join(&engine_file_name);
 
+    if engine_file.exists() {
+        return Ok(false);
+    }
+
+    // members of a workspace or parallel CI jobs often install the same engine at once
+    fs::create_dir_all(&dirs.engines_install_dir)?;
+    let _lock = atomic::lock(
+        &dirs
+            .engines_install_dir
+            .join(format!(".{engine_name}.lock")),
+    )
+    .await?;
     if !engine_file.exists() {
-        println!("Could not find matching version of Godot engine locally, downloading...");
+        progress.event(Event::Resolve {
+            version: config.version.to_string(),
+            engine: engine_name.clone(),
+        });
 
         let zip_file_name = format!("{}.zip", &engine_name);
 
@@ -51,10 +159,24 @@ pub async fn ensure_version_installed(project: &Project) -> rootcause::Result<()
         };
         let zip_file_path = dirs.cache_dir.join("engines").join(&zip_file_name);
 
-        download_from_github(&zip_file_path, zip_file_name_remote, project).await?;
-
-        println!("Extracting archive...");
-        archive::extract(&zip_file_path, &engine_dir, Some(true))?;
+        download_from_github(
+            &zip_file_path,
+            zip_file_name_remote,
+            project,
+            downloader,
+            progress,
+        )
+        .await?;
+
+        progress.event(Event::Extract {
+            target: engine_dir.clone(),
+        });
+        archive::extract(&zip_file_path, &engine_dir, Some(true)).map_err(|source| {
+            Error::Extract {
+                archive: zip_file_path.clone(),
+                source,
+            }
+        })?;
         for entry in [
             config.get_engine_file_name(false),
             config.get_engine_file_name(true),
@@ -66,16 +188,15 @@ pub async fn ensure_version_installed(project: &Project) -> rootcause::Result<()
             }
         }
 
-        println!("Reclaiming disk space...");
-        if dirs.download_dir.is_dir() {
-            fs::remove_dir_all(&dirs.download_dir)?;
-        }
+        progress.event(Event::Done {
+            name: engine_name,
+            path: engine_dir,
+        });
 
-        println!(
-            "Successfully installed Godot engine version {}",
-            &engine_name
-        )
+        hooks::run(project, Hook::PostInstall, progress).await?;
+
+        return Ok(true);
     }
 
-    Ok(())
+    Ok(false)
 }
@@ -0,0 +1,428 @@
+use crate::Error;
+use crate::project::config::{EngineDownloadSource, UpgradePolicy};
+use crate::project::diagnostics::{self, Diagnostic, Severity};
+use crate::project::format::ConfigFormat;
+use crate::util::download::{self, DownloadError, Downloader};
+use crate::util::{atomic, dirs};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+/// Name of the settings file in gdm's configuration directory.
+pub const FILE_NAME: &str = "config.toml";
+
+/// The user's settings, shared by all of their projects.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Use the Mono build of the engine for new projects.
+    pub mono: bool,
+    /// Where new projects download the engine from.
+    pub download_source: EngineDownloadSource,
+    /// Base URLs replacing where a source's releases are downloaded from, e.g. an internal mirror.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub mirrors: BTreeMap<EngineDownloadSource, String>,
+    /// Token for GitHub's API, which limits how often anonymous users can look up releases.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub github_token: Option<String>,
+    /// Which releases `gdm upgrade` moves projects to.
+    pub upgrade: UpgradePolicy,
+    /// Proxy for every request, e.g. `http://proxy.example:8080`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Where engines are installed, instead of the platform's data directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<PathBuf>,
+    /// Where downloads are cached, instead of the platform's cache directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Settings {
+    pub const KEYS: &[&str] = &[
+        "mono",
+        "download_source",
+        "mirrors",
+        "github_token",
+        "upgrade",
+        "proxy",
+        "data_dir",
+        "cache_dir",
+    ];
+
+    /// Where the settings are stored, below `home` when one is set.
+    pub fn path(home: Option<&Path>) -> crate::Result<PathBuf> {
+        Ok(match home {
+            Some(home) => home.join(FILE_NAME),
+            None => dirs::platform()?.config_dir().join(FILE_NAME),
+        })
+    }
+
+    /// Reads the settings, the defaults when there is no settings file.
+    pub fn load(home: Option<&Path>) -> crate::Result<Settings> {
+        let path = Settings::path(home)?;
+        let text = read(&path)?;
+        Settings::parse(&text).map_err(|diagnostic| Error::ConfigParse {
+            path,
+            diagnostic: Box::new(diagnostic),
+        })
+    }
+
+    /// Reads only `data_dir` and `cache_dir`, which every command needs, so a mistake in another
+    /// setting only fails the commands using it and `gdm config --global set` can still fix it.
+    pub fn load_dirs(home: Option<&Path>) -> crate::Result<Settings> {
+        #[derive(Deserialize)]
+        struct Dirs {
+            data_dir: Option<PathBuf>,
+            cache_dir: Option<PathBuf>,
+        }
+
+        let path = Settings::path(home)?;
+        let text = read(&path)?;
+        let document =
+            ConfigFormat::Toml
+                .parse(&text)
+                .map_err(|diagnostic| Error::ConfigParse {
+                    path: path.clone(),
+                    diagnostic: Box::new(diagnostic),
+                })?;
+        let dirs: Dirs =
+            serde_path_to_error::deserialize(document).map_err(|error| Error::ConfigParse {
+                path,
+                diagnostic: Box::new(diagnostics::parse_error(
+                    error.path().to_string(),
+                    error.into_inner(),
+                )),
+            })?;
+
+        Ok(Settings {
+            data_dir: dirs.data_dir,
+            cache_dir: dirs.cache_dir,
+            ..Settings::default()
+        })
+    }
+
+    /// The value of a key like `mono` or `mirrors.github`, `None` when it is not set.
+    pub fn get(&self, key: &str) -> crate::Result<Option<Value>> {
+        check_key(key)?;
+        let settings = serde_json::to_value(self).expect("settings serialize to JSON");
+
+        Ok(key
+            .split('.')
+            .try_fold(&settings, |value, part| value.get(part))
+            .cloned())
+    }
+
+    /// Changes a key in the settings file below `home`, removing it when `value` is `None`.
+    ///
+    /// Values are read like [`candidate_values`]. The file keeps its comments and is only written
+    /// when the new settings are valid, which also repairs a file with an invalid setting.
+    pub fn set(home: Option<&Path>, key: &str, value: Option<&str>) -> crate::Result<Settings> {
+        check_key(key)?;
+        let path = Settings::path(home)?;
+        let text = read(&path)?;
+        // only TOML syntax errors are beyond repair, invalid settings may be the ones being fixed
+        let document: toml_edit::DocumentMut = text.parse().map_err(|_| Error::ConfigParse {
+            path: path.clone(),
+            diagnostic: Box::new(
+                ConfigFormat::Toml
+                    .parse(&text)
+                    .expect_err("toml_edit and toml agree on the syntax"),
+            ),
+        })?;
+
+        let candidates = match value {
+            None => vec![None],
+            Some(value) => candidate_values(value)
+                .iter()
+                // TOML has no null
+                .filter_map(|value| value.serialize(toml_edit::ser::ValueSerializer::new()).ok())
+                .map(Some)
+                .collect(),
+        };
+
+        let mut first_error = None;
+        for candidate in candidates {
+            let mut document = document.clone();
+            assign(&mut document, key, candidate)?;
+            let text = document.to_string();
+
+            match Settings::parse(&text) {
+                Ok(settings) => {
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    atomic::write(&path, &text)?;
+                    return Ok(settings);
+                }
+                Err(diagnostic) => {
+                    first_error.get_or_insert(diagnostic);
+                }
+            }
+        }
+
+        let error = first_error.expect("there is always a candidate");
+        Err(Error::InvalidSetting {
+            key: key.to_string(),
+            reason: match error.suggestion {
+                Some(suggestion) => format!("{}, did you mean `{suggestion}`?", error.message),
+                None => error.message,
+            },
+        })
+    }
+
+    /// A downloader using the proxy, token and mirrors of the settings.
+    pub fn downloader(&self) -> Result<Downloader, DownloadError> {
+        let client = download::make_client(self.proxy.as_deref())?;
+        Ok(self.configure(Downloader::with_client(client)))
+    }
+
+    /// Applies the token and mirrors to a downloader with a client of its own.
+    pub fn configure(&self, mut downloader: Downloader) -> Downloader {
+        downloader = downloader.github_token(self.github_token.clone());
+        for (source, mirror) in &self.mirrors {
+            downloader = downloader.mirror(source.download_url(), mirror.clone());
+        }
+        downloader
+    }
+
+    fn parse(text: &str) -> Result<Settings, Diagnostic> {
+        let document = ConfigFormat::Toml.parse(text)?;
+        let settings: Settings = serde_path_to_error::deserialize(document).map_err(|error| {
+            let mut diagnostic =
+                diagnostics::parse_error(error.path().to_string(), error.into_inner());
+            let key = diagnostic.json_path.rsplit('.').next().unwrap_or_default();
+            (diagnostic.line, diagnostic.column) =
+                diagnostics::locate_key(text, key, ConfigFormat::Toml).unzip();
+            diagnostic
+        })?;
+
+        let invalid = |json_path: String, message: String| {
+            let key = json_path.rsplit('.').next().unwrap_or_default();
+            let (line, column) = diagnostics::locate_key(text, key, ConfigFormat::Toml).unzip();
+            Diagnostic {
+                severity: Severity::Error,
+                message,
+                json_path,
+                line,
+                column,
+                suggestion: None,
+            }
+        };
+        if let Some(proxy) = &settings.proxy
+            && let Err(error) = reqwest::Proxy::all(proxy)
+        {
+            return Err(invalid(
+                "proxy".to_string(),
+                format!("`{proxy}` is not a valid proxy: {error}"),
+            ));
+        }
+        for (source, mirror) in &settings.mirrors {
+            if let Err(error) = reqwest::Url::parse(mirror) {
+                return Err(invalid(
+                    format!("mirrors.{source}"),
+                    format!("`{mirror}` is not a valid URL: {error}"),
+                ));
+            }
+        }
+
+        Ok(settings)
+    }
+}
+
+/// What a value given on the command line may mean, JSON like `true` or `["a", "b"]` first and
+/// then the plain string, so `4.3-stable` does not need quotes.
+pub(crate) fn candidate_values(raw: &str) -> Vec<Value> {
+    serde_json::from_str(raw)
+        .ok()
+        .into_iter()
+        .chain([Value::String(raw.to_string())])
+        .collect()
+}
+
+/// Only known keys can be read and written, `mirrors` is the only table.
+fn check_key(key: &str) -> crate::Result<()> {
+    let invalid = |reason: String| Error::InvalidSetting {
+        key: key.to_string(),
+        reason,
+    };
+
+    let (name, nested) = match key.split_once('.') {
+        Some((name, nested)) => (name, Some(nested)),
+        None => (key, None),
+    };
+    if !Settings::KEYS.contains(&name) {
+        return Err(invalid(
+            match diagnostics::suggest(name, Settings::KEYS.iter().copied()) {
+                Some(suggestion) => format!("unknown setting, did you mean `{suggestion}`?"),
+                None => "unknown setting".to_string(),
+            },
+        ));
+    }
+    match nested {
+        Some(nested) if name != "mirrors" || nested.contains('.') => {
+            Err(invalid(format!("`{name}` has no keys of its own")))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn assign(
+    document: &mut toml_edit::DocumentMut,
+    key: &str,
+    value: Option<toml_edit::Value>,
+) -> crate::Result<()> {
+    let (table, name) = match key.split_once('.') {
+        None => (
+            document.as_table_mut() as &mut dyn toml_edit::TableLike,
+            key,
+        ),
+        Some((parent, name)) => {
+            let item = document.entry(parent).or_insert(toml_edit::table());
+            let Some(table) = item.as_table_like_mut() else {
+                return Err(Error::InvalidSetting {
+                    key: key.to_string(),
+                    reason: format!("`{parent}` in the settings file is not a table"),
+                });
+            };
+            (table, name)
+        }
+    };
+
+    match value {
+        Some(mut value) => match table.get_mut(name) {
+            Some(existing) => {
+                // comments around a value stay when it changes
+                if let Some(old) = existing.as_value() {
+                    *value.decor_mut() = old.decor().clone();
+                }
+                *existing = toml_edit::Item::Value(value);
+            }
+            None => {
+                table.insert(name, toml_edit::Item::Value(value));
+            }
+        },
+        None => {
+            table.remove(name);
+        }
+    }
+    Ok(())
+}
+
+fn read(path: &Path) -> io::Result<String> {
+    match fs::read_to_string(path) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh home directory for one test, removed again by the test.
+    fn home(name: &str) -> PathBuf {
+        let home = std::env::temp_dir().join(format!("gdm-settings-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        fs::create_dir_all(&home).unwrap();
+        home
+    }
+
+    #[test]
+    fn loads_defaults_without_a_file() {
+        let home = home("defaults");
+        let settings = Settings::load(Some(&home)).unwrap();
+        fs::remove_dir_all(&home).unwrap();
+
+        assert!(!settings.mono);
+        assert_eq!(settings.upgrade, UpgradePolicy::default());
+        assert!(settings.mirrors.is_empty());
+    }
+
+    #[test]
+    fn sets_and_unsets_keys_keeping_comments() {
+        let home = home("set");
+        fs::write(
+            home.join(FILE_NAME),
+            "# my settings\nmono = false # for now\n",
+        )
+        .unwrap();
+
+        let settings = Settings::set(Some(&home), "mono", Some("true")).unwrap();
+        assert!(settings.mono);
+        Settings::set(
+            Some(&home),
+            "mirrors.github",
+            Some("https://mirror.example/godot"),
+        )
+        .unwrap();
+        let text = fs::read_to_string(home.join(FILE_NAME)).unwrap();
+        let settings = Settings::set(Some(&home), "mono", None).unwrap();
+        let unset = Settings::load(Some(&home)).unwrap();
+        fs::remove_dir_all(&home).unwrap();
+
+        assert_eq!(
+            text,
+            "# my settings\nmono = true # for now\n\n[mirrors]\ngithub = \"https://mirror.example/godot\"\n"
+        );
+        assert!(!settings.mono);
+        assert_eq!(
+            unset.get("mirrors.github").unwrap(),
+            Some(Value::String("https://mirror.example/godot".to_string()))
+        );
+    }
+
+    #[test]
+    fn refuses_invalid_values() {
+        let home = home("invalid-value");
+        let result = Settings::set(Some(&home), "upgrade", Some("minr"));
+        let unknown = Settings::set(Some(&home), "mnoo", Some("true"));
+        let written = home.join(FILE_NAME).exists();
+        fs::remove_dir_all(&home).unwrap();
+
+        match result {
+            Err(Error::InvalidSetting { key, reason }) => {
+                assert_eq!(key, "upgrade");
+                assert!(reason.ends_with("did you mean `minor`?"), "{reason}");
+            }
+            result => panic!("expected an invalid setting, got {result:?}"),
+        }
+        assert!(matches!(unknown, Err(Error::InvalidSetting { .. })));
+        assert!(!written);
+    }
+
+    #[test]
+    fn repairs_an_invalid_file() {
+        let home = home("repair");
+        fs::write(
+            home.join(FILE_NAME),
+            "data_dir = \"/opt/gdm\"\n\n[mirrors]\ngithub = \"not a url\"\n",
+        )
+        .unwrap();
+
+        let broken = Settings::load(Some(&home));
+        // commands that only need the directories keep working
+        let dirs = Settings::load_dirs(Some(&home)).unwrap();
+        Settings::set(Some(&home), "mirrors.github", None).unwrap();
+        let repaired = Settings::load(Some(&home));
+        fs::remove_dir_all(&home).unwrap();
+
+        assert!(matches!(broken, Err(Error::ConfigParse { .. })));
+        assert_eq!(dirs.data_dir, Some(PathBuf::from("/opt/gdm")));
+        assert_eq!(repaired.unwrap().data_dir, Some(PathBuf::from("/opt/gdm")));
+    }
+
+    #[test]
+    fn reads_values_as_json_first() {
+        assert_eq!(
+            candidate_values("true"),
+            [Value::Bool(true), Value::String("true".to_string())]
+        );
+        assert_eq!(
+            candidate_values("4.3-stable"),
+            [Value::String("4.3-stable".to_string())]
+        );
+    }
+}
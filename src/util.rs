@@ -1,4 +1,8 @@
 pub mod archive;
+pub mod atomic;
+pub mod config_file;
 pub mod dirs;
 pub mod download;
 pub mod os;
+pub mod process;
+pub mod progress;
@@ -5,9 +5,9 @@ use zip::result::ZipError;
 
 #[derive(Error, Debug)]
 pub enum ExtractError {
-    #[error("IO Error")]
+    #[error("{0}")]
     IoError(#[from] std::io::Error),
-    #[error("Zip Error")]
+    #[error("{0}")]
     ZipError(#[from] zip::result::ZipError),
 }
 
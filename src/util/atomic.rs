@@ -0,0 +1,36 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Replaces the file's content in one step, an interrupted write leaves the old content in place.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    // same directory, a rename across file systems would not be atomic
+    let tmp_path = path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()));
+
+    let result = File::create(&tmp_path).and_then(|mut file| {
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Holds an exclusive lock on `path` until the returned file is dropped, waiting while another
+/// process holds it, e.g. a second gdm installing the same engine.
+pub async fn lock(path: &Path) -> io::Result<File> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let file = File::options()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&path)?;
+        file.lock()?;
+        Ok(file)
+    })
+    .await?
+}
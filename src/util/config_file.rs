@@ -0,0 +1,383 @@
+use std::ops::Range;
+use std::{fs, io, path::Path};
+
+/// A parsed Godot `ConfigFile` (the format used by `project.godot` and `export_presets.cfg`).
+///
+/// Values are kept as their raw Variant literals; use the typed getters on [`Section`] to read them.
+#[derive(Debug, Default)]
+pub struct ConfigFile {
+    sections: Vec<Section>,
+}
+
+#[derive(Debug)]
+pub struct Section {
+    pub name: String,
+    entries: Vec<(String, String)>,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> io::Result<ConfigFile> {
+        Ok(ConfigFile::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn parse(text: &str) -> ConfigFile {
+        // keys before the first header (e.g. `config_version` in project.godot) live in an unnamed section
+        let mut sections = vec![Section {
+            name: String::new(),
+            entries: Vec::new(),
+        }];
+
+        for line in lines(text) {
+            match line.kind {
+                LineKind::Header(name) => sections.push(Section {
+                    name: name.to_string(),
+                    entries: Vec::new(),
+                }),
+                LineKind::Entry(key, value) => sections
+                    .last_mut()
+                    .expect("there is always at least one section")
+                    .entries
+                    .push((key, value)),
+                LineKind::Other => {}
+            }
+        }
+
+        ConfigFile { sections }
+    }
+
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    pub fn sections(&self) -> impl Iterator<Item = &Section> {
+        self.sections
+            .iter()
+            .filter(|section| !section.name.is_empty())
+    }
+
+    /// Keys that appear before the first section header.
+    pub fn global(&self) -> &Section {
+        &self.sections[0]
+    }
+}
+
+impl Section {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn get_string(&self, key: &str) -> Option<String> {
+        self.get(key).and_then(unquote)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key)? {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        self.get(key)?.parse().ok()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+/// Decodes a quoted Variant string literal, e.g. `"Linux/X11"`.
+pub fn unquote(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut result = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+
+    Some(result)
+}
+
+/// Extracts every string literal from a value such as `PackedStringArray("4.2", "Forward Plus")`.
+pub fn string_literals(value: &str) -> Vec<String> {
+    let mut literals = Vec::new();
+    let mut current: Option<String> = None;
+    let mut escaped = false;
+
+    for c in value.chars() {
+        let Some(literal) = current.as_mut() else {
+            if c == '"' {
+                current = Some(String::new());
+            }
+            continue;
+        };
+
+        if escaped {
+            literal.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            literals.extend(current.take());
+        } else {
+            literal.push(c);
+        }
+    }
+
+    literals
+}
+
+/// Replaces the entries of a section with raw `(key, value)` pairs, keeping the rest of the text.
+///
+/// The section is appended when it does not exist yet.
+pub fn replace_section(text: &str, name: &str, entries: &[(String, String)]) -> String {
+    let body: String = entries
+        .iter()
+        .map(|(key, value)| format!("{key}={value}\n"))
+        .collect();
+
+    let Some(range) = section_range(text, name) else {
+        let mut result = text.to_string();
+        if !result.is_empty() {
+            if !result.ends_with('\n') {
+                result.push('\n');
+            }
+            result.push('\n');
+        }
+        result.push_str(&format!("[{name}]\n\n{body}"));
+        return result;
+    };
+
+    // Godot separates sections with an empty line
+    if range.end == text.len() {
+        format!("{}\n{body}", &text[..range.start])
+    } else {
+        format!("{}\n{body}\n{}", &text[..range.start], &text[range.end..])
+    }
+}
+
+/// Where the entries of a section are in the text, from after its header up to the next one.
+pub fn section_range(text: &str, name: &str) -> Option<Range<usize>> {
+    let lines = lines(text);
+    let header = lines
+        .iter()
+        .position(|line| matches!(line.kind, LineKind::Header(header) if header == name))?;
+
+    let start = lines[header].range.end;
+    let end = lines[header + 1..]
+        .iter()
+        .find(|line| matches!(line.kind, LineKind::Header(_)))
+        .map_or(text.len(), |line| line.range.start);
+    Some(start..end)
+}
+
+/// A logical line of a `ConfigFile` and where it is in the text, values may span several lines.
+struct Line<'a> {
+    range: Range<usize>,
+    kind: LineKind<'a>,
+}
+
+enum LineKind<'a> {
+    Header(&'a str),
+    Entry(String, String),
+    /// Empty lines and comments.
+    Other,
+}
+
+fn lines(text: &str) -> Vec<Line<'_>> {
+    let mut result = Vec::new();
+    let mut offset = 0;
+
+    let mut raw_lines = text.split_inclusive('\n');
+    while let Some(raw_line) = raw_lines.next() {
+        let start = offset;
+        offset += raw_line.len();
+
+        let line = raw_line.trim();
+        let kind = if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            LineKind::Other
+        } else if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            LineKind::Header(name)
+        } else if let Some((key, value)) = split_entry(line) {
+            // arrays, dictionaries and strings may continue over several lines
+            let mut value = value.trim().to_string();
+            while !is_complete(&value) {
+                match raw_lines.next() {
+                    Some(next) => {
+                        offset += next.len();
+                        value.push('\n');
+                        value.push_str(next.trim_end_matches(['\n', '\r']));
+                    }
+                    None => break,
+                }
+            }
+            LineKind::Entry(key, value)
+        } else {
+            LineKind::Other
+        };
+
+        result.push(Line {
+            range: start..offset,
+            kind,
+        });
+    }
+
+    result
+}
+
+/// Splits `key=value`, keys with special characters are quoted and may contain `=` themselves.
+fn split_entry(line: &str) -> Option<(String, &str)> {
+    if line.starts_with('"') {
+        let mut escaped = false;
+        let end = line.char_indices().skip(1).find_map(|(index, c)| {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => return Some(index),
+                _ => {}
+            }
+            None
+        })?;
+        let value = line[end + 1..].trim_start().strip_prefix('=')?;
+        return Some((unquote(&line[..=end])?, value));
+    }
+
+    let (key, value) = line.split_once('=')?;
+    Some((key.trim().to_string(), value))
+}
+
+fn is_complete(value: &str) -> bool {
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in value.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    !in_string && depth <= 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT: &str = r#"; Engine configuration file.
+config_version=5
+
+[application]
+
+config/name="Game"
+config/features=PackedStringArray("4.2", "Forward Plus")
+
+[input]
+
+jump={
+"deadzone": 0.5,
+"events": []
+}
+"ui a=b"="quoted"
+
+[gdm]
+
+version="4.2-stable"
+"#;
+
+    #[test]
+    fn parses_sections_and_global_keys() {
+        let file = ConfigFile::parse(PROJECT);
+
+        assert_eq!(file.global().get_int("config_version"), Some(5));
+        let names: Vec<&str> = file
+            .sections()
+            .map(|section| section.name.as_str())
+            .collect();
+        assert_eq!(names, ["application", "input", "gdm"]);
+        let application = file.section("application").unwrap();
+        assert_eq!(
+            application.get_string("config/name").as_deref(),
+            Some("Game")
+        );
+        assert_eq!(
+            string_literals(application.get("config/features").unwrap()),
+            ["4.2", "Forward Plus"]
+        );
+    }
+
+    #[test]
+    fn parses_multi_line_values() {
+        let file = ConfigFile::parse(PROJECT);
+        let input = file.section("input").unwrap();
+
+        assert_eq!(
+            input.get("jump"),
+            Some("{\n\"deadzone\": 0.5,\n\"events\": []\n}")
+        );
+        // the entry after a multi-line value is still read
+        assert_eq!(input.get_string("ui a=b").as_deref(), Some("quoted"));
+    }
+
+    #[test]
+    fn parses_quoted_keys() {
+        let file = ConfigFile::parse("[section]\n\"a \\\"b\\\"=c\" = 1\nplain=2\n");
+        let section = file.section("section").unwrap();
+
+        assert_eq!(section.get_int("a \"b\"=c"), Some(1));
+        assert_eq!(section.get_int("plain"), Some(2));
+    }
+
+    #[test]
+    fn unquotes_escapes() {
+        assert_eq!(unquote(r#""a\"b\nc""#).as_deref(), Some("a\"b\nc"));
+        assert_eq!(unquote("plain"), None);
+    }
+
+    #[test]
+    fn replaces_a_section_in_place() {
+        let entries = [("version".to_string(), "\"4.3-stable\"".to_string())];
+        let text = replace_section(PROJECT, "input", &entries);
+
+        assert!(text.contains("[input]\n\nversion=\"4.3-stable\"\n\n[gdm]"));
+        assert!(text.starts_with("; Engine configuration file."));
+        assert!(!text.contains("jump"));
+    }
+
+    #[test]
+    fn appends_a_missing_section() {
+        let entries = [("a".to_string(), "1".to_string())];
+
+        assert_eq!(
+            replace_section("config_version=5\n", "gdm", &entries),
+            "config_version=5\n\n[gdm]\n\na=1\n"
+        );
+    }
+}
@@ -1,8 +1,10 @@
+use crate::project::format::ConfigFormat;
+use crate::settings::Settings;
 use directories::ProjectDirs;
 use path_clean::PathClean;
-use rootcause::report;
-use std::env;
-use std::path::PathBuf;
+use rootcause::{bail, report};
+use std::path::{Path, PathBuf};
+use std::{env, io};
 use tokio::fs;
 
 #[derive(Debug, Clone)]
@@ -10,56 +12,199 @@ pub struct Dirs {
     pub project_dir: PathBuf,
     pub absolute_project_dir: PathBuf,
     pub cache_dir: PathBuf,
+    pub data_dir: PathBuf,
     pub download_dir: PathBuf,
     pub engines_install_dir: PathBuf,
 }
 
+impl Dirs {
+    /// The same global directories pointed at the project in `project_dir`, which must exist.
+    pub fn with_project(mut self, project_dir: PathBuf) -> io::Result<Dirs> {
+        self.project_dir = project_dir.clean();
+        self.absolute_project_dir = dunce::canonicalize(&self.project_dir)?;
+        Ok(self)
+    }
+
+    /// Per-project state kept by gdm outside of the project itself, such as engine logs.
+    pub fn project_data_dir(&self) -> PathBuf {
+        let name = self
+            .absolute_project_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let hash = stable_hash(self.absolute_project_dir.as_os_str().as_encoded_bytes());
+
+        self.data_dir
+            .join("projects")
+            .join(format!("{name}-{hash:016x}"))
+    }
+}
+
+/// gdm's directories for the project in `project_path`, or else the nearest project from the
+/// current directory up, the way git finds `.git`, see [`find_project`].
+///
+/// Without a project anywhere up the tree the current directory is used, so loading the project
+/// reports it missing there.
 pub async fn init(project_path: Option<PathBuf>) -> rootcause::Result<Dirs> {
-    init0(project_path, true).await
+    let result = init_no_project().await?;
+    let project_dir = match project_path {
+        Some(path) => path.clean(),
+        None => {
+            let current = env::current_dir()?;
+            find_project(&current).unwrap_or(current)
+        }
+    };
+    if !project_dir.is_dir() {
+        bail!("Project directory {} does not exist", project_dir.display());
+    }
+    Ok(result.with_project(project_dir)?)
+}
+
+/// gdm's directories for a new project in `project_path` or the current directory, creating the
+/// directory when it does not exist yet.
+pub async fn init_new(project_path: Option<PathBuf>) -> rootcause::Result<Dirs> {
+    let result = init_no_project().await?;
+    let project_dir = project_path.unwrap_or(result.project_dir.clone()).clean();
+    fs::create_dir_all(&project_dir).await?;
+    Ok(result.with_project(project_dir)?)
+}
+
+/// The nearest of `start` and its parents that is the root of a project, the first one holding
+/// a project.godot, a gdm.toml or a project.json.
+///
+/// Version pins are too common outside of Godot projects, e.g. asdf writes `~/.tool-versions`,
+/// so they only count next to a project.godot.
+pub fn find_project(start: &Path) -> Option<PathBuf> {
+    let markers = [ConfigFormat::Godot, ConfigFormat::Toml, ConfigFormat::Json];
+    start
+        .ancestors()
+        .find(|dir| {
+            markers
+                .iter()
+                .any(|format| dir.join(format.file_name()).is_file())
+        })
+        .map(Path::to_path_buf)
+}
+
+/// The directory set by `GDM_USER_HOME`, which keeps everything of gdm in one place.
+pub fn user_home() -> Option<PathBuf> {
+    env::var_os("GDM_USER_HOME").map(PathBuf::from)
 }
 
-async fn init0(project_path: Option<PathBuf>, init_project: bool) -> rootcause::Result<Dirs> {
+/// The platform's directories for gdm, used unless a home directory is set.
+pub fn platform() -> rootcause::Result<ProjectDirs> {
+    // empty strings for qualifier and org name are fine
+    ProjectDirs::from("", "", "gdm").ok_or(report!("Could not read global directories"))
+}
+
+/// gdm's own directories, all kept below `home` when given instead of the platform's defaults.
+///
+/// Otherwise the data and cache directories of the user's settings replace the platform's.
+pub fn global(home: Option<PathBuf>, settings: &Settings) -> rootcause::Result<Dirs> {
     let engines_install_dir: PathBuf;
     let download_dir: PathBuf;
     let cache_dir: PathBuf;
+    let data_dir: PathBuf;
 
-    match env::var("GDM_USER_HOME").ok().map(PathBuf::from) {
+    match home {
         None => {
-            // empty strings for qualifier and org name are fine
-            let project_dirs = ProjectDirs::from("", "", "gdm")
-                .ok_or(report!("Could not read global directories"))?;
-            cache_dir = project_dirs.cache_dir().to_path_buf();
-            let data_local_dir = project_dirs.data_local_dir().to_path_buf();
+            let project_dirs = platform()?;
+            cache_dir = settings
+                .cache_dir
+                .clone()
+                .unwrap_or_else(|| project_dirs.cache_dir().to_path_buf());
+            data_dir = settings
+                .data_dir
+                .clone()
+                .unwrap_or_else(|| project_dirs.data_local_dir().to_path_buf());
 
-            engines_install_dir = data_local_dir.join("engines");
+            engines_install_dir = data_dir.join("engines");
             download_dir = cache_dir.join("downloads");
         }
         Some(gdm_home) => {
             cache_dir = gdm_home.join("cache");
             engines_install_dir = gdm_home.join("engines");
             download_dir = gdm_home.join("downloads");
+            data_dir = gdm_home;
         }
     }
 
-    let mut result = Dirs {
+    Ok(Dirs {
         project_dir: PathBuf::from("."),
         absolute_project_dir: PathBuf::from("."),
 
         cache_dir,
+        data_dir,
         download_dir,
 
         engines_install_dir,
-    };
+    })
+}
+
+pub async fn init_no_project() -> rootcause::Result<Dirs> {
+    let home = user_home();
+    let settings = Settings::load_dirs(home.as_deref())?;
+    global(home, &settings)
+}
+
+/// Total size of all files below `path`.
+pub fn disk_usage(path: &Path) -> io::Result<u64> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
 
-    if init_project {
-        result.project_dir = project_path.unwrap_or(result.project_dir).clean();
-        fs::create_dir_all(&result.project_dir).await?;
-        result.absolute_project_dir = dunce::canonicalize(&result.project_dir)?
+    let mut total = 0;
+    for entry in std::fs::read_dir(path)? {
+        total += disk_usage(&entry?.path())?;
     }
+    Ok(total)
+}
 
-    Ok(result)
+/// FNV-1a hash, stable across builds so names derived from it survive gdm updates.
+pub(crate) fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
 }
 
-pub async fn init_no_project() -> rootcause::Result<Dirs> {
-    init0(None, false).await
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn finds_the_nearest_project() {
+        let home = env::temp_dir().join(format!("gdm-find-project-{}", std::process::id()));
+        let game = home.join("projects/game");
+        let tool = home.join("projects/game/tools/editor");
+        let fresh = home.join("projects/fresh");
+        for dir in [
+            home.join("notes"),
+            game.join("scenes"),
+            tool.join("src"),
+            fresh.join("scripts"),
+        ] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        // written by `asdf global godot 4.3`
+        fs::write(home.join(".tool-versions"), "godot 4.3\n").unwrap();
+        fs::write(game.join("project.godot"), "config_version=5\n").unwrap();
+        fs::write(game.join("project.json"), "{}").unwrap();
+        fs::write(tool.join("gdm.toml"), "").unwrap();
+        // `gdm init` writes only a project.json, project.godot follows on the first run
+        fs::write(fresh.join("project.json"), "{}").unwrap();
+
+        let found = [
+            find_project(&home.join("notes")),
+            find_project(&home),
+            find_project(&game.join("scenes")),
+            find_project(&tool.join("src")),
+            find_project(&fresh.join("scripts")),
+        ];
+        fs::remove_dir_all(&home).unwrap();
+
+        assert_eq!(found, [None, None, Some(game), Some(tool), Some(fresh)]);
+    }
 }
@@ -1,9 +1,8 @@
-use std::{cmp, fs, io::Write, path::PathBuf};
+use std::{fs, io::Write, path::Path, path::PathBuf};
 
-use super::archive;
+use super::progress::{Event, Progress};
 use crate::util::dirs::Dirs;
 use futures_util::StreamExt;
-use indicatif::{ProgressBar, ProgressStyle};
 use rand::Rng;
 use rand::prelude::ThreadRng;
 use reqwest::Client;
@@ -13,81 +12,269 @@ static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_P
 
 #[derive(Error, Debug)]
 pub enum DownloadError {
-    #[error("IO Error")]
+    #[error("{0}")]
     IoError(#[from] std::io::Error),
-    #[error("HTTP Error")]
+    #[error("{0}")]
     HttpError(#[from] reqwest::Error),
-    #[error("Unknown Error")]
-    Unknown,
-    #[error("Zip Error")]
-    ZipError(#[from] zip::result::ZipError),
-    #[error("Zip Extract Error")]
-    ExtraftError(#[from] archive::ExtractError),
+    #[error("Server responded with status {status}")]
+    Status { url: String, status: u16 },
+    #[error("Network access is disabled in offline mode, could not fetch {0}")]
+    Offline(String),
 }
 
-pub fn make_client() -> Result<Client, DownloadError> {
-    let client = Client::builder().user_agent(APP_USER_AGENT).build()?;
-    Ok(client)
+impl DownloadError {
+    /// The URL of the failed request, if the error belongs to one.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            DownloadError::HttpError(error) => error.url().map(|url| url.as_str()),
+            DownloadError::Status { url, .. } | DownloadError::Offline(url) => Some(url),
+            DownloadError::IoError(_) => None,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, DownloadError::Status { status: 404, .. })
+    }
+}
+
+/// Builds gdm's HTTP client, sending every request through `proxy` when given.
+pub fn make_client(proxy: Option<&str>) -> Result<Client, DownloadError> {
+    let mut builder = Client::builder().user_agent(APP_USER_AGENT);
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+    Ok(builder.build()?)
 }
 
-pub async fn download_file(
-    url: String,
-    local_path: &PathBuf,
-    dirs: &Dirs,
-) -> Result<u64, DownloadError> {
-    let download_dir = &dirs.download_dir;
-    fs::create_dir_all(download_dir)?;
+/// Performs gdm's HTTP requests, sharing one client between them.
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    client: Client,
+    offline: bool,
+    github_token: Option<String>,
+    mirrors: Vec<(String, String)>,
+}
 
-    if local_path.exists() {
-        fs::remove_file(local_path)?;
+impl Downloader {
+    pub fn new() -> Result<Downloader, DownloadError> {
+        Ok(Downloader::with_client(make_client(None)?))
     }
 
-    let mut rng = ThreadRng::default();
-    let rand_int = rng.next_u32();
+    /// Uses a preconfigured client, e.g. one with a proxy or custom certificates.
+    pub fn with_client(client: Client) -> Downloader {
+        Downloader {
+            client,
+            offline: false,
+            github_token: None,
+            mirrors: Vec::new(),
+        }
+    }
 
-    let tmp_file = download_dir.join(format!("download-{rand_int}"));
+    /// Authenticates requests to GitHub, which raises the rate limit of its API.
+    pub fn github_token(mut self, token: Option<String>) -> Downloader {
+        self.github_token = token;
+        self
+    }
 
-    let client = make_client()?;
-    let result = client.get(&url).send().await?;
+    /// Fetches URLs starting with `prefix` from `mirror` instead, keeping the rest of the URL.
+    ///
+    /// A later mirror for the same prefix replaces the earlier one.
+    pub fn mirror(mut self, prefix: impl Into<String>, mirror: impl Into<String>) -> Downloader {
+        let prefix = prefix.into();
+        self.mirrors.retain(|(existing, _)| *existing != prefix);
+        self.mirrors.push((prefix, mirror.into()));
+        self
+    }
 
-    if result.status().is_success() {
-        let total_size = result.content_length().unwrap_or(1024);
+    /// In offline mode every request fails with [`DownloadError::Offline`] instead of touching the network.
+    pub fn offline(mut self, offline: bool) -> Downloader {
+        self.offline = offline;
+        self
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
 
-        let pb = ProgressBar::new(total_size);
-        pb.set_style(ProgressStyle::default_bar()
-        .template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})").unwrap()
-        .progress_chars("#>-"));
-        pb.set_message(format!("Downloading {}", url));
+    async fn get(&self, url: &str) -> Result<reqwest::Response, DownloadError> {
+        if self.offline {
+            return Err(DownloadError::Offline(url.to_string()));
+        }
+
+        let mirrored = self.mirrors.iter().find_map(|(prefix, mirror)| {
+            let rest = url.strip_prefix(prefix.as_str())?;
+            Some(format!("{}{rest}", mirror.trim_end_matches('/')))
+        });
+        let mut request = self.client.get(mirrored.as_deref().unwrap_or(url));
 
-        let mut file = fs::File::create(&tmp_file)?;
-        let mut downloaded: u64 = 0;
-        let mut stream = result.bytes_stream();
-        while let Some(item) = stream.next().await {
-            let chunk = item?;
-            file.write_all(&chunk)?;
-            downloaded += chunk.len() as u64;
-            pb.set_position(cmp::min(downloaded, total_size));
+        // mirrors never see the token
+        if let Some(token) = &self.github_token
+            && mirrored.is_none()
+            && (url.starts_with("https://api.github.com/")
+                || url.starts_with("https://github.com/"))
+        {
+            request = request.bearer_auth(token);
+        }
+        Ok(request.send().await?)
+    }
+
+    pub async fn download_file(
+        &self,
+        url: String,
+        local_path: &PathBuf,
+        dirs: &Dirs,
+        progress: &dyn Progress,
+    ) -> Result<u64, DownloadError> {
+        let download_dir = &dirs.download_dir;
+        fs::create_dir_all(download_dir)?;
+
+        if local_path.exists() {
+            fs::remove_file(local_path)?;
+        }
+
+        let mut rng = ThreadRng::default();
+        let rand_int = rng.next_u32();
+
+        let tmp_file = download_dir.join(format!("download-{rand_int}"));
+
+        let result = self.get(&url).await?;
+
+        if result.status().is_success() {
+            let total = result.content_length();
+            progress.event(Event::DownloadStart {
+                url: url.clone(),
+                total,
+            });
+
+            let copied = async {
+                let mut file = fs::File::create(&tmp_file)?;
+                let mut downloaded: u64 = 0;
+                let mut stream = result.bytes_stream();
+                while let Some(item) = stream.next().await {
+                    let chunk = item?;
+                    file.write_all(&chunk)?;
+                    downloaded += chunk.len() as u64;
+                    progress.event(Event::DownloadProgress { downloaded, total });
+                }
+                progress.event(Event::DownloadFinish { downloaded });
+                local_path
+                    .parent()
+                    .map(fs::create_dir_all)
+                    .expect("Unable to create target directory")?;
+
+                fs::copy(&tmp_file, local_path)?;
+                Ok(downloaded)
+            }
+            .await;
+            // other downloads may be running in the same directory, so only this one's file goes
+            let _ = fs::remove_file(&tmp_file);
+            copied
+        } else {
+            Err(DownloadError::Status {
+                url,
+                status: result.status().as_u16(),
+            })
+        }
+    }
+
+    pub async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: String,
+    ) -> Result<T, DownloadError> {
+        let result = self.get(&url).await?;
+        if result.status().is_success() {
+            Ok(result.json::<T>().await?)
+        } else {
+            Err(DownloadError::Status {
+                url,
+                status: result.status().as_u16(),
+            })
+        }
+    }
+
+    /// Fetches every page of a JSON list, following the `Link: <url>; rel="next"` headers of
+    /// paginated APIs like GitHub's.
+    pub async fn get_json_pages<T: serde::de::DeserializeOwned>(
+        &self,
+        url: String,
+    ) -> Result<Vec<T>, DownloadError> {
+        let mut items = Vec::new();
+        let mut next = Some(url);
+        while let Some(url) = next {
+            let result = self.get(&url).await?;
+            if !result.status().is_success() {
+                return Err(DownloadError::Status {
+                    url,
+                    status: result.status().as_u16(),
+                });
+            }
+            next = result
+                .headers()
+                .get(reqwest::header::LINK)
+                .and_then(|link| link.to_str().ok())
+                .and_then(next_page);
+            items.extend(result.json::<Vec<T>>().await?);
+        }
+        Ok(items)
+    }
+
+    /// Fetches a text file, `None` when the server does not have it.
+    pub async fn get_text(&self, url: String) -> Result<Option<String>, DownloadError> {
+        let result = self.get(&url).await?;
+        if result.status() == reqwest::StatusCode::NOT_FOUND {
+            Ok(None)
+        } else if result.status().is_success() {
+            Ok(Some(result.text().await?))
+        } else {
+            Err(DownloadError::Status {
+                url,
+                status: result.status().as_u16(),
+            })
         }
-        pb.set_position(total_size);
-        pb.finish_with_message(format!("Downloaded {} bytes", downloaded));
-        local_path
-            .parent()
-            .map(fs::create_dir_all)
-            .expect("Unable to create target directory")?;
-
-        fs::copy(&tmp_file, local_path)?;
-        Ok(downloaded)
-    } else {
-        Err(DownloadError::Unknown) //TODO status code error?
     }
 }
 
-pub async fn get_json<T: serde::de::DeserializeOwned>(url: String) -> Result<T, DownloadError> {
-    let client = make_client()?;
-    let result = client.get(&url).send().await?;
-    if result.status().is_success() {
-        Ok(result.json::<T>().await?)
-    } else {
-        Err(DownloadError::Unknown) //TODO status code error?
+/// The URL of the next page in a `Link` header, e.g. `<https://...?page=2>; rel="next"`.
+fn next_page(link: &str) -> Option<String> {
+    link.split(',').find_map(|entry| {
+        let mut parts = entry.split(';');
+        let url = parts.next()?.trim().strip_prefix('<')?.strip_suffix('>')?;
+        parts
+            .any(|part| part.trim() == "rel=\"next\"")
+            .then(|| url.to_string())
+    })
+}
+
+/// Hex encoded SHA-512 of the file's content.
+pub fn sha512(path: &Path) -> Result<String, DownloadError> {
+    use sha2::{Digest, Sha512};
+
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha512::new();
+    std::io::copy(&mut file, &mut hasher)?;
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_next_page() {
+        let link = r#"<https://api.github.com/repositories/15634981/releases?per_page=100&page=2>; rel="next", <https://api.github.com/repositories/15634981/releases?per_page=100&page=3>; rel="last""#;
+
+        assert_eq!(
+            next_page(link).as_deref(),
+            Some("https://api.github.com/repositories/15634981/releases?per_page=100&page=2")
+        );
+        assert_eq!(
+            next_page(r#"<https://api.github.com/releases?page=1>; rel="prev""#),
+            None
+        );
     }
 }
@@ -1,4 +1,4 @@
-use rootcause::bail;
+use crate::Error;
 use std::cmp::PartialEq;
 use std::path::PathBuf;
 
@@ -81,14 +81,18 @@ impl OS {
         CURRENT_ARCH.clone()
     }
 
-    pub fn get_os_string(&self, mono: bool) -> rootcause::Result<&str> {
+    pub fn get_os_string(&self, mono: bool) -> crate::Result<&str> {
         match (self, self.architecture(), mono) {
             (OS::Windows, Arch::X86, false) => Ok("win32"),
             (OS::Windows, Arch::X86, true) => Ok("mono_win32"),
             (OS::Windows, Arch::X64, false) => Ok("win64"),
             (OS::Windows, Arch::X64, true) => Ok("mono_win64"),
-            (OS::Windows, Arch::ARM32, _) => bail!("ARM32 is not supported on Windows"),
-            (OS::Windows, Arch::ARM64, _) => bail!("ARM64 is not supported on Windows"),
+            (OS::Windows, Arch::ARM32, _) => {
+                Err(Error::UnsupportedPlatform("Windows on ARM32".to_string()))
+            }
+            (OS::Windows, Arch::ARM64, _) => {
+                Err(Error::UnsupportedPlatform("Windows on ARM64".to_string()))
+            }
             (OS::Linux, Arch::X86, false) => Ok("linux.x86_32"),
             (OS::Linux, Arch::X86, true) => Ok("mono_linux_x86_32"),
             (OS::Linux, Arch::X64, false) => Ok("linux.x86_64"),
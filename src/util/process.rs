@@ -0,0 +1,136 @@
+use super::progress::Progress;
+use std::io;
+use std::process::{ExitStatus, Stdio};
+use tokio::process::{Child, Command};
+
+/// Builds a command running `command_line` through the platform's shell.
+pub fn shell(command_line: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(command_line);
+        command
+    }
+    #[cfg(not(windows))]
+    {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(command_line);
+        command
+    }
+}
+
+/// Where a child process writes its output, stderr when `progress` keeps stdout to itself.
+pub fn stdout(progress: &dyn Progress) -> Stdio {
+    match progress.redirect_output() {
+        true => Stdio::from(io::stderr()),
+        false => Stdio::inherit(),
+    }
+}
+
+/// Waits for the child to exit, forwarding SIGTERM and SIGINT received by gdm to it.
+///
+/// Ctrl+C in a terminal already interrupts the whole foreground process group, so SIGINT is not
+/// forwarded while gdm and the child are that group. A `kill -INT` sent to gdm from elsewhere in
+/// that case cannot be told apart from Ctrl+C and is not forwarded either, SIGTERM always is.
+#[cfg(unix)]
+pub async fn wait(mut child: Child) -> io::Result<ExitStatus> {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+
+    loop {
+        let signal = tokio::select! {
+            status = child.wait() => return status,
+            _ = interrupt.recv() => libc::SIGINT,
+            _ = terminate.recv() => libc::SIGTERM,
+        };
+
+        let Some(pid) = child.id() else {
+            continue;
+        };
+        // SAFETY: these calls have no memory requirements, the pid belongs to our own child
+        unsafe {
+            let pid = pid as libc::pid_t;
+            let group = libc::getpgrp();
+            let foreground = [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO]
+                .into_iter()
+                .find(|&fd| libc::isatty(fd) == 1)
+                .is_some_and(|fd| libc::tcgetpgrp(fd) == group);
+            if signal == libc::SIGINT && foreground && libc::getpgid(pid) == group {
+                continue;
+            }
+            libc::kill(pid, signal);
+        }
+    }
+}
+
+/// Waits for the child to exit, Ctrl+C is delivered to the child by the console itself.
+#[cfg(windows)]
+pub async fn wait(mut child: Child) -> io::Result<ExitStatus> {
+    loop {
+        tokio::select! {
+            status = child.wait() => return status,
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    }
+}
+
+/// The exit code gdm should report for the child, following the shell convention of 128 + signal.
+pub fn exit_code(status: &ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+
+    1
+}
+
+/// Human readable description of how the child exited.
+pub fn describe(status: &ExitStatus) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return match signal_name(signal) {
+                Some(name) => format!("was terminated by signal {signal} ({name})"),
+                None => format!("was terminated by signal {signal}"),
+            };
+        }
+    }
+
+    match status.code() {
+        Some(0) => "exited successfully".to_string(),
+        // windows reports crashes as NTSTATUS codes, which are only recognizable in hex
+        Some(code) if code < 0 => format!("exited with code {code:#X}"),
+        Some(code) => format!("exited with code {code}"),
+        None => "exited".to_string(),
+    }
+}
+
+#[cfg(unix)]
+fn signal_name(signal: i32) -> Option<&'static str> {
+    let name = match signal {
+        libc::SIGHUP => "SIGHUP, hangup",
+        libc::SIGINT => "SIGINT, interrupted",
+        libc::SIGQUIT => "SIGQUIT, quit",
+        libc::SIGILL => "SIGILL, illegal instruction",
+        libc::SIGTRAP => "SIGTRAP, trace trap",
+        libc::SIGABRT => "SIGABRT, aborted",
+        libc::SIGBUS => "SIGBUS, bus error",
+        libc::SIGFPE => "SIGFPE, floating point exception",
+        libc::SIGKILL => "SIGKILL, killed",
+        libc::SIGSEGV => "SIGSEGV, segmentation fault",
+        libc::SIGPIPE => "SIGPIPE, broken pipe",
+        libc::SIGTERM => "SIGTERM, terminated",
+        _ => return None,
+    };
+    Some(name)
+}
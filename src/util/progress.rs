@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+/// Something gdm is doing that a user interface may want to show.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// An engine version is missing locally and is about to be installed.
+    Resolve {
+        version: String,
+        engine: String,
+    },
+    DownloadStart {
+        url: String,
+        total: Option<u64>,
+    },
+    DownloadProgress {
+        downloaded: u64,
+        total: Option<u64>,
+    },
+    DownloadFinish {
+        downloaded: u64,
+    },
+    /// A download is being checked against the checksums published with the release.
+    Verify {
+        file: PathBuf,
+    },
+    Extract {
+        target: PathBuf,
+    },
+    /// An engine or its export templates finished installing.
+    Done {
+        name: String,
+        path: PathBuf,
+    },
+    /// A hook or task command is about to run.
+    Command {
+        label: String,
+        command: String,
+    },
+    Message(String),
+}
+
+/// Receives the events of long running library operations, e.g. to draw progress bars.
+pub trait Progress: Send + Sync {
+    fn event(&self, event: Event);
+
+    /// Whether the engine, tasks and hooks write their output to stderr, keeping stdout to the
+    /// caller, e.g. for a JSON document.
+    fn redirect_output(&self) -> bool {
+        false
+    }
+}
+
+/// Ignores all events, for embedding gdm without any output.
+pub struct Silent;
+
+impl Progress for Silent {
+    fn event(&self, _event: Event) {}
+}
+
+impl<F: Fn(Event) + Send + Sync> Progress for F {
+    fn event(&self, event: Event) {
+        self(event)
+    }
+}
+
+/// Forwards events to a channel, for user interfaces running on their own task.
+impl Progress for tokio::sync::mpsc::UnboundedSender<Event> {
+    fn event(&self, event: Event) {
+        // the receiver going away only means nobody is interested anymore
+        let _ = self.send(event);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::Error;
+use crate::project::diagnostics::{self, Diagnostic, Severity};
+use crate::project::format::ConfigFormat;
+use path_clean::PathClean;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the file at the root of a workspace, listing its members.
+pub const FILE_NAME: &str = "gdm.workspace.toml";
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkspaceFile {
+    members: Vec<PathBuf>,
+}
+
+/// Several projects in one repository managed together, e.g. a game and the tools built for it.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    /// Directory holding the workspace file.
+    pub root: PathBuf,
+    /// Directories of the member projects, relative to the root.
+    pub members: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// The nearest workspace from `start` up, `None` outside of one.
+    pub fn find(start: &Path) -> crate::Result<Option<Workspace>> {
+        match start.ancestors().find(|dir| dir.join(FILE_NAME).is_file()) {
+            Some(root) => Workspace::load(root).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the workspace file in `root`.
+    pub fn load(root: &Path) -> crate::Result<Workspace> {
+        let path = root.join(FILE_NAME);
+        let text = fs::read_to_string(&path)?;
+        let invalid = |diagnostic| Error::ConfigParse {
+            path: path.clone(),
+            diagnostic: Box::new(diagnostic),
+        };
+
+        let document = ConfigFormat::Toml.parse(&text).map_err(invalid)?;
+        let file: WorkspaceFile = serde_path_to_error::deserialize(document).map_err(|error| {
+            let mut diagnostic =
+                diagnostics::parse_error(error.path().to_string(), error.into_inner());
+            let key = diagnostic.json_path.split('.').next().unwrap_or_default();
+            (diagnostic.line, diagnostic.column) =
+                diagnostics::locate_key(&text, key, ConfigFormat::Toml).unzip();
+            invalid(diagnostic)
+        })?;
+
+        let members: Vec<PathBuf> = file
+            .members
+            .into_iter()
+            .map(|member| member.clean())
+            .collect();
+        // members are found by walking up to the workspace, which never reaches one outside it
+        if let Some(index) = members
+            .iter()
+            .position(|member| member.is_absolute() || member.starts_with(".."))
+        {
+            let (line, column) =
+                diagnostics::locate_key(&text, "members", ConfigFormat::Toml).unzip();
+            return Err(invalid(Diagnostic {
+                severity: Severity::Error,
+                message: format!(
+                    "`{}` is outside the workspace, members must be directories below it",
+                    members[index].display()
+                ),
+                json_path: format!("members[{index}]"),
+                line,
+                column,
+                suggestion: None,
+            }));
+        }
+
+        Ok(Workspace {
+            root: root.to_path_buf(),
+            members,
+        })
+    }
+
+    /// Directory of the member `name`, its path as listed in the file or the directory's name.
+    pub fn member(&self, name: &str) -> Option<PathBuf> {
+        let name = Path::new(name).clean();
+        self.members
+            .iter()
+            .find(|member| **member == name || member.file_name() == Some(name.as_os_str()))
+            .map(|member| self.root.join(member))
+    }
+
+    /// Directories of all members, in the order the file lists them.
+    pub fn member_dirs(&self) -> Vec<PathBuf> {
+        self.members
+            .iter()
+            .map(|member| self.root.join(member))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh workspace root whose file lists `members`, a TOML array.
+    fn workspace(name: &str, members: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("gdm-workspace-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join(FILE_NAME), format!("members = {members}\n")).unwrap();
+        root
+    }
+
+    #[test]
+    fn finds_the_workspace_from_a_member() {
+        let root = workspace("find", r#"["game", "tools/level-editor/"]"#);
+        let scenes = root.join("tools/level-editor/scenes");
+        fs::create_dir_all(&scenes).unwrap();
+        let found = Workspace::find(&scenes).unwrap();
+        let outside = Workspace::find(root.parent().unwrap()).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let found = found.unwrap();
+        assert_eq!(found.root, root);
+        assert_eq!(
+            found.members,
+            [PathBuf::from("game"), PathBuf::from("tools/level-editor")]
+        );
+        assert!(outside.is_none());
+    }
+
+    #[test]
+    fn finds_the_nearest_of_nested_workspaces() {
+        let root = workspace("nested", r#"["game", "tools"]"#);
+        let tools = root.join("tools");
+        fs::create_dir_all(tools.join("editor/addons")).unwrap();
+        fs::write(tools.join(FILE_NAME), "members = [\"editor\"]\n").unwrap();
+        let found = Workspace::find(&tools.join("editor/addons")).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let found = found.unwrap();
+        assert_eq!(found.root, tools);
+        assert_eq!(found.members, [PathBuf::from("editor")]);
+    }
+
+    #[test]
+    fn looks_up_members() {
+        let root = workspace(
+            "member",
+            r#"["game", "tools/level-editor", "tests/assets"]"#,
+        );
+        let workspace = Workspace::load(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            workspace.member("level-editor"),
+            Some(root.join("tools/level-editor"))
+        );
+        assert_eq!(workspace.member("./game/"), Some(root.join("game")));
+        assert_eq!(
+            workspace.member("tests/assets"),
+            Some(root.join("tests/assets"))
+        );
+        assert_eq!(workspace.member("server"), None);
+        assert_eq!(
+            workspace.member_dirs(),
+            [
+                root.join("game"),
+                root.join("tools/level-editor"),
+                root.join("tests/assets")
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_members_outside_the_workspace() {
+        let root = workspace("outside", r#"["game", "tools/../../other"]"#);
+        let error = Workspace::load(&root).unwrap_err();
+        fs::remove_dir_all(&root).unwrap();
+
+        let Error::ConfigParse { path, diagnostic } = error else {
+            panic!("expected a parse error, got {error:?}");
+        };
+        assert_eq!(path, root.join(FILE_NAME));
+        assert_eq!(diagnostic.json_path, "members[1]");
+        assert!(
+            diagnostic
+                .message
+                .contains("`../other` is outside the workspace")
+        );
+        assert_eq!(diagnostic.line, Some(1));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let root = workspace("unknown", r#"["game"]"#);
+        fs::write(root.join(FILE_NAME), "members = [\"game\"]\nmembrs = []\n").unwrap();
+        let error = Workspace::load(&root).unwrap_err();
+        fs::remove_dir_all(&root).unwrap();
+
+        let Error::ConfigParse { diagnostic, .. } = error else {
+            panic!("expected a parse error, got {error:?}");
+        };
+        assert_eq!(diagnostic.line, Some(2));
+    }
+}